@@ -0,0 +1,85 @@
+#[macro_use] extern crate criterion;
+extern crate sindra;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use criterion::{BenchmarkId, Criterion, black_box};
+
+use sindra::{Identifier, SyntaxContext};
+use sindra::scope::{DeclarationStore, MemoryScope, MemoryStore, Stack, SymbolScope, SymbolStore};
+
+const DEPTHS: [usize; 3] = [8, 64, 256];
+
+/// Builds a chain of `depth` scopes, each defining a few local identifiers, and returns the
+/// innermost scope.
+fn symbol_chain(depth: usize) -> Rc<RefCell<SymbolScope<usize>>> {
+    let mut scope = Rc::new(RefCell::new(SymbolScope::default()));
+    for level in 0..depth {
+        for local in 0..4 {
+            let ident = Identifier::new(&format!("local_{}_{}", level, local));
+            scope.borrow_mut().define(ident, level);
+        }
+        scope = scope.push();
+    }
+    scope
+}
+
+fn memory_chain(depth: usize) -> Rc<RefCell<MemoryScope<(), i64>>> {
+    let mut scope = Rc::new(RefCell::new(MemoryScope::default()));
+    for level in 0..depth {
+        for local in 0..4 {
+            let ident = Identifier::new(&format!("local_{}_{}", level, local));
+            scope.borrow_mut().declare_init(ident, (), level as i64);
+        }
+        scope = scope.push();
+    }
+    scope
+}
+
+fn resolve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resolve");
+    for &depth in &DEPTHS {
+        let scope = symbol_chain(depth);
+        let outermost = Identifier::new("local_0_0");
+        let missing = Identifier::new("missing");
+        // no binding in its own context, so resolution falls back to the root context
+        let marked = outermost.with_context(SyntaxContext::fresh());
+        group.bench_with_input(BenchmarkId::new("outermost", depth), &depth, |b, _| {
+            b.iter(|| scope.borrow().resolve(black_box(&outermost)))
+        });
+        group.bench_with_input(BenchmarkId::new("marked", depth), &depth, |b, _| {
+            b.iter(|| scope.borrow().resolve(black_box(&marked)))
+        });
+        group.bench_with_input(BenchmarkId::new("missing", depth), &depth, |b, _| {
+            b.iter(|| scope.borrow().resolve(black_box(&missing)))
+        });
+    }
+    group.finish();
+}
+
+fn memory(c: &mut Criterion) {
+    let mut group = c.benchmark_group("memory");
+    for &depth in &DEPTHS {
+        let scope = memory_chain(depth);
+        let outermost = Identifier::new("local_0_0");
+        let marked = outermost.with_context(SyntaxContext::fresh());
+        group.bench_with_input(BenchmarkId::new("get", depth), &depth, |b, _| {
+            b.iter(|| scope.borrow().get(black_box(&outermost)))
+        });
+        group.bench_with_input(BenchmarkId::new("get_marked", depth), &depth, |b, _| {
+            b.iter(|| scope.borrow().get(black_box(&marked)))
+        });
+        group.bench_with_input(BenchmarkId::new("set", depth), &depth, |b, _| {
+            b.iter(|| scope.borrow_mut().set(black_box(outermost), 1))
+        });
+    }
+    group.finish();
+}
+
+fn intern(c: &mut Criterion) {
+    c.bench_function("intern", |b| b.iter(|| Identifier::new(black_box("some_identifier"))));
+}
+
+criterion_group!(benches, resolve, memory, intern);
+criterion_main!(benches);
@@ -1,17 +1,432 @@
 //! Identifier type.
+//!
+//! Identifiers are interned: the text of each distinct identifier is stored once in a global
+//! interner, and an `Identifier` is a `Copy` handle the size of a `u32`. Hashing, comparing and
+//! copying identifiers (e.g. on every symbol table lookup) therefore never touches the text.
+//!
+//! Each identifier also carries a `SyntaxContext`, allowing macro expanders to keep identifiers
+//! introduced by different expansions apart (hygiene). Identifiers in the root context refer to
+//! their text directly; the interner also stores each distinct pair of text and (non-root)
+//! context used by an identifier, so that the handle stays the same size.
+//!
+//! Qualified names (e.g. `std::io::Write`) are represented by `IdentPath`, which can be parsed
+//! from and formatted to text using a configurable `PathSyntax`.
 
+use std::collections::HashMap;
 use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::Mutex;
 
 use node::Annotated;
 
+/// Bit set in the handle of an identifier outside the root context, whose remaining bits index
+/// the interned pairs of text and context (rather than the interned strings).
+const MARKED: u32 = 1 << 31;
+
+/// String interner, mapping each distinct string to a unique index, and each distinct pair of text
+/// and non-root syntax context to a unique (marked) handle. Interned strings are never freed,
+/// which allows their text to be handed out with a `'static` lifetime.
+#[derive(Default)]
+struct Interner {
+    indices: HashMap<&'static str, u32>,
+    strings: Vec<&'static str>,
+    marked_indices: HashMap<(u32, SyntaxContext), u32>,
+    marked: Vec<(u32, SyntaxContext)>,
+}
+impl Interner {
+    fn intern(&mut self, text: &str) -> u32 {
+        if let Some(&index) = self.indices.get(text) {
+            return index;
+        }
+        let index = self.strings.len() as u32;
+        assert!(index < MARKED, "too many distinct identifiers");
+        let text: &'static str = Box::leak(text.to_string().into_boxed_str());
+        self.strings.push(text);
+        self.indices.insert(text, index);
+        index
+    }
+    /// Handle of the identifier with the specified text index and context.
+    fn handle(&mut self, name: u32, context: SyntaxContext) -> u32 {
+        if context.is_root() {
+            return name;
+        }
+        if let Some(&handle) = self.marked_indices.get(&(name, context)) {
+            return handle;
+        }
+        let index = self.marked.len() as u32;
+        assert!(index < MARKED, "too many distinct identifiers");
+        self.marked.push((name, context));
+        self.marked_indices.insert((name, context), index | MARKED);
+        index | MARKED
+    }
+    /// Text index and context of the identifier with the specified handle.
+    fn parts(&self, handle: u32) -> (u32, SyntaxContext) {
+        if handle & MARKED == 0 {
+            (handle, SyntaxContext::ROOT)
+        } else {
+            self.marked[(handle & !MARKED) as usize]
+        }
+    }
+}
+
+lazy_static! {
+    static ref INTERNER: Mutex<Interner> = Mutex::new(Interner::default());
+    /// Outer context of each syntax context, indexed by context (the root context is its own
+    /// outer context).
+    static ref CONTEXT_PARENTS: Mutex<Vec<SyntaxContext>> = Mutex::new(vec![SyntaxContext::ROOT]);
+}
+
+/// Syntax context (or mark) of an identifier, distinguishing identifiers introduced by different
+/// macro expansions.
+///
+/// Identifiers written directly in the source have the root context. A macro expander should
+/// create a fresh context for each expansion, and apply it to the identifiers introduced by the
+/// macro body (but not to identifiers passed in as macro arguments). Identifiers with the same
+/// text but different contexts are distinct, so bindings introduced by an expansion can neither
+/// capture nor be captured by user bindings.
+///
+/// Each context other than the root records the context it was created within (its outer
+/// context). When resolving an identifier, scopes strip marks outward: an identifier which has no
+/// binding in its own context resolves in its outer context, and so on up to the root context,
+/// so expanded code can still refer to the names visible where the macro was expanded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SyntaxContext(u32);
+impl SyntaxContext {
+    /// Root context, used for identifiers not introduced by any macro expansion.
+    pub const ROOT: SyntaxContext = SyntaxContext(0);
+    /// Creates a new context within the root context, distinct from every other context created
+    /// in this process.
+    pub fn fresh() -> SyntaxContext {
+        SyntaxContext::ROOT.nested()
+    }
+    /// Creates a new context within this context (e.g. for the expansion of a macro invoked by
+    /// code introduced by another expansion), distinct from every other context created in this
+    /// process.
+    pub fn nested(&self) -> SyntaxContext {
+        let mut parents = CONTEXT_PARENTS.lock().unwrap();
+        parents.push(*self);
+        SyntaxContext((parents.len() - 1) as u32)
+    }
+    /// Outer context of this context (the context it was created within), or `None` if this is
+    /// the root context.
+    pub fn parent(&self) -> Option<SyntaxContext> {
+        if self.is_root() {
+            None
+        } else {
+            CONTEXT_PARENTS.lock().unwrap().get(self.0 as usize).cloned()
+        }
+    }
+    /// Returns `true` if this is the root context.
+    pub fn is_root(&self) -> bool { *self == SyntaxContext::ROOT }
+    /// Index of this context. Indices are unique within the same process.
+    pub fn index(&self) -> u32 { self.0 }
+}
+
 /// Identifier in a program. Used for lookups into symbol tables / memory.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Identifier(pub String);
+///
+/// Identifiers are equal (and have equal hashes) if they have the same text and the same syntax
+/// context, regardless of how they were created.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Identifier(u32);
+impl Identifier {
+    /// Creates (interning if necessary) the identifier with the specified text, in the root
+    /// syntax context.
+    pub fn new(text: &str) -> Identifier {
+        Identifier(INTERNER.lock().unwrap().intern(text))
+    }
+    /// Creates a fresh identifier with the specified text: a generated identifier (in a new
+    /// syntax context) which is distinct from every other identifier, including those with the
+    /// same text.
+    pub fn gensym(text: &str) -> Identifier {
+        Identifier::new(text).with_context(SyntaxContext::fresh())
+    }
+    /// Text of this identifier.
+    pub fn as_str(&self) -> &'static str {
+        let interner = INTERNER.lock().unwrap();
+        interner.strings[interner.parts(self.0).0 as usize]
+    }
+    /// Index of this identifier's text in the interner. Indices are unique among identifier
+    /// texts within the same process, but depend on the order in which identifiers were first
+    /// created.
+    pub fn index(&self) -> u32 {
+        if self.0 & MARKED == 0 {
+            return self.0;
+        }
+        INTERNER.lock().unwrap().parts(self.0).0
+    }
+    /// Syntax context of this identifier.
+    pub fn context(&self) -> SyntaxContext {
+        if self.0 & MARKED == 0 {
+            return SyntaxContext::ROOT;
+        }
+        INTERNER.lock().unwrap().parts(self.0).1
+    }
+    /// Creates an identifier with the same text as this identifier, in the specified syntax
+    /// context.
+    pub fn with_context(&self, context: SyntaxContext) -> Identifier {
+        let mut interner = INTERNER.lock().unwrap();
+        let (name, _) = interner.parts(self.0);
+        Identifier(interner.handle(name, context))
+    }
+    /// Identifier with the same text as this identifier, in the outer context of this
+    /// identifier's context, or `None` if this identifier is in the root context.
+    pub fn strip_mark(&self) -> Option<Identifier> {
+        self.context().parent().map(|context| self.with_context(context))
+    }
+    /// Iterates over this identifier and the identifiers obtained by repeatedly stripping marks
+    /// (see `strip_mark`), ending with the identifier in the root context: the identifiers a
+    /// binding for this identifier is searched for under, in order.
+    pub fn outward(&self) -> impl Iterator<Item = Identifier> {
+        ::std::iter::successors(Some(*self), Identifier::strip_mark)
+    }
+}
 impl Annotated for Identifier { type Annotation = usize; }
 
+impl<'a> From<&'a str> for Identifier {
+    fn from(text: &'a str) -> Identifier { Identifier::new(text) }
+}
+impl From<String> for Identifier {
+    fn from(text: String) -> Identifier { Identifier::new(&text) }
+}
 
 impl fmt::Display for Identifier {
     fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.as_str())
+    }
+}
+impl fmt::Debug for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        let mut debug = f.debug_tuple("Identifier");
+        debug.field(&self.as_str());
+        let context = self.context();
+        if !context.is_root() {
+            debug.field(&context.0);
+        }
+        debug.finish()
+    }
+}
+
+/// Serialised form of an identifier: its text, and (unless in the root context) its context index
+/// along with the epoch of the process which created the context.
+///
+/// Context indices are unique within a process (and so stable within a serialised payload), and
+/// the epoch distinguishes the contexts of different processes. Contexts created by this process
+/// are restored exactly; contexts created elsewhere are each mapped to a single fresh context of
+/// this process, so deserialising the same data more than once produces equal identifiers. The
+/// outer contexts of contexts created elsewhere are not preserved: they are restored as contexts
+/// within the root context.
+///
+/// Human-readable formats write identifiers in the root context as plain text (so that they can be
+/// used as map keys, e.g. in JSON), and read either form. Other formats, which may not be
+/// self-describing, always use the tuple.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct IdentifierRepr<S>(S, Option<(u32, u64)>);
+/// Serialised form of an identifier in human-readable formats.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ReadableRepr {
+    Root(String),
+    Full(IdentifierRepr<String>),
+}
+#[cfg(feature = "serde")]
+lazy_static! {
+    /// Random identifier of this process, distinguishing its syntax contexts in serialised data.
+    static ref EPOCH: u64 = {
+        use std::hash::{BuildHasher, Hasher};
+        let mut hasher = ::std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u32(::std::process::id());
+        hasher.finish()
+    };
+    /// Contexts of this process allocated to contexts of other processes, keyed by epoch and
+    /// context index.
+    static ref IMPORTED_CONTEXTS: Mutex<HashMap<(u64, u32), SyntaxContext>> =
+        Mutex::new(HashMap::new());
+}
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for Identifier {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let context = self.context();
+        if context.is_root() {
+            if serializer.is_human_readable() {
+                return serializer.serialize_str(self.as_str());
+            }
+            return IdentifierRepr(self.as_str(), None).serialize(serializer);
+        }
+        IdentifierRepr(self.as_str(), Some((context.0, *EPOCH))).serialize(serializer)
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for Identifier {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Identifier, D::Error> {
+        let IdentifierRepr(text, context) = if deserializer.is_human_readable() {
+            match ReadableRepr::deserialize(deserializer)? {
+                ReadableRepr::Root(text) => IdentifierRepr(text, None),
+                ReadableRepr::Full(repr) => repr,
+            }
+        } else {
+            IdentifierRepr::<String>::deserialize(deserializer)?
+        };
+        let context = match context {
+            None => SyntaxContext::ROOT,
+            Some((context, epoch)) if epoch == *EPOCH => {
+                if context as usize >= CONTEXT_PARENTS.lock().unwrap().len() {
+                    return Err(::serde::de::Error::custom(
+                        format!("unknown syntax context: {}", context)));
+                }
+                SyntaxContext(context)
+            },
+            Some((context, epoch)) => {
+                *IMPORTED_CONTEXTS.lock().unwrap().entry((epoch, context))
+                    .or_insert_with(SyntaxContext::fresh)
+            },
+        };
+        Ok(Identifier::new(&text).with_context(context))
+    }
+}
+
+/// Path of identifiers naming an item through namespace or member scopes (e.g. `std::io::Write`
+/// or `obj.field`).
+///
+/// `IdentPath` dereferences to a slice of its segments, so it can be passed directly to path
+/// resolution methods such as `MemberStore::resolve_path`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IdentPath {
+    segments: Vec<Identifier>,
+}
+impl IdentPath {
+    /// Creates a path from its segments.
+    pub fn new(segments: Vec<Identifier>) -> IdentPath {
+        IdentPath { segments }
+    }
+    /// Parses a path from text, using the default syntax (segments separated by `::`).
+    ///
+    /// # Failures
+    /// See `PathSyntax::parse`.
+    pub fn parse(text: &str) -> Result<IdentPath, PathError> {
+        PathSyntax::default().parse(text)
+    }
+    /// Segments of this path.
+    pub fn segments(&self) -> &[Identifier] { &self.segments }
+    /// Appends a segment to the end of this path.
+    pub fn push(&mut self, segment: Identifier) { self.segments.push(segment); }
+    /// Returns `true` if this path has more than one segment.
+    pub fn is_qualified(&self) -> bool { self.segments.len() > 1 }
+    /// Path of the item containing the item named by this path (i.e. this path without its
+    /// final segment), or `None` if this path has fewer than two segments.
+    pub fn parent(&self) -> Option<IdentPath> {
+        if self.is_qualified() {
+            Some(IdentPath::new(self.segments[..self.segments.len() - 1].to_vec()))
+        } else {
+            None
+        }
+    }
+}
+impl Deref for IdentPath {
+    type Target = [Identifier];
+    fn deref(&self) -> &[Identifier] { &self.segments }
+}
+impl From<Identifier> for IdentPath {
+    fn from(ident: Identifier) -> IdentPath { IdentPath::new(vec![ident]) }
+}
+impl From<Vec<Identifier>> for IdentPath {
+    fn from(segments: Vec<Identifier>) -> IdentPath { IdentPath::new(segments) }
+}
+impl FromStr for IdentPath {
+    type Err = PathError;
+    fn from_str(text: &str) -> Result<IdentPath, PathError> { IdentPath::parse(text) }
+}
+/// Formats the path using the default syntax (segments separated by `::`).
+impl fmt::Display for IdentPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        write!(f, "{}", PathSyntax::default().format(self))
+    }
+}
+
+/// Error resulting from parsing an `IdentPath`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// The text contains no segments.
+    Empty,
+    /// The segment at the specified byte offset is empty (e.g. `a::::b`, or a leading or trailing
+    /// separator).
+    EmptySegment(usize),
+}
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        match *self {
+            PathError::Empty => write!(f, "empty path"),
+            PathError::EmptySegment(offset) => write!(f, "empty path segment at offset {}", offset),
+        }
+    }
+}
+
+/// Syntax for parsing and formatting `IdentPath`s: the set of separators accepted between
+/// segments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathSyntax {
+    separators: Vec<String>,
+}
+impl Default for PathSyntax {
+    fn default() -> PathSyntax { PathSyntax::new(&["::"]) }
+}
+impl PathSyntax {
+    /// Creates a path syntax accepting any of the specified separators when parsing (e.g.
+    /// `&["::", "."]`). The first separator is used when formatting.
+    ///
+    /// # Panics
+    /// Panics if `separators` is empty or contains an empty separator.
+    pub fn new(separators: &[&str]) -> PathSyntax {
+        assert!(!separators.is_empty() && separators.iter().all(|sep| !sep.is_empty()),
+            "path syntax requires at least one non-empty separator");
+        PathSyntax { separators: separators.iter().map(|sep| sep.to_string()).collect() }
+    }
+    /// Parses a path from text. Segments are not otherwise validated; the text between
+    /// separators is used verbatim.
+    ///
+    /// # Failures
+    /// Fails with `PathError::Empty` if the text is empty, or `PathError::EmptySegment` if any
+    /// segment is empty.
+    pub fn parse(&self, text: &str) -> Result<IdentPath, PathError> {
+        if text.is_empty() {
+            return Err(PathError::Empty);
+        }
+        let mut segments = vec![];
+        let mut start = 0;
+        let mut pos = 0;
+        while pos < text.len() {
+            // prefer the longest separator at this position (e.g. `::` over `:`)
+            let sep_len = self.separators.iter()
+                .filter(|sep| text[pos..].starts_with(sep.as_str()))
+                .map(|sep| sep.len())
+                .max();
+            match sep_len {
+                Some(sep_len) => {
+                    segments.push(segment(text, start, pos)?);
+                    pos += sep_len;
+                    start = pos;
+                },
+                None => {
+                    pos += text[pos..].chars().next().map_or(1, char::len_utf8);
+                }
+            }
+        }
+        segments.push(segment(text, start, pos)?);
+        Ok(IdentPath::new(segments))
+    }
+    /// Formats a path, separating segments with the first separator of this syntax.
+    pub fn format(&self, path: &IdentPath) -> String {
+        path.segments.iter().map(Identifier::as_str).collect::<Vec<_>>()
+            .join(&self.separators[0])
+    }
+}
+fn segment(text: &str, start: usize, end: usize) -> Result<Identifier, PathError> {
+    if start == end {
+        Err(PathError::EmptySegment(start))
+    } else {
+        Ok(Identifier::new(&text[start..end]))
     }
 }
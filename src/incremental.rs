@@ -0,0 +1,95 @@
+//! Support for incremental reparsing.
+//!
+//! After an edit to a `SourceFile`, subtrees of the previous syntax tree whose spans do not
+//! intersect the changed range can be reused in the new tree. A parser collects these candidate
+//! subtrees in a `ReuseCache`, and checks the cache before parsing at each position: when a
+//! subtree is available at that position, it is reused in place of parsing, and parsing resumes at
+//! the end of the reused subtree.
+
+use std::collections::HashMap;
+
+use node::{Annotated, Node};
+use source::ChangedRange;
+use span::Span;
+
+/// Trait for syntax tree items which contain spans that need to be relocated when reused after an
+/// edit.
+pub trait ShiftSpans {
+    /// Relocates all spans contained within this item according to `change`. Only called on items
+    /// unaffected by `change`.
+    fn shift_spans(&mut self, change: &ChangedRange);
+}
+impl<T: Annotated + ShiftSpans> ShiftSpans for Node<T> {
+    fn shift_spans(&mut self, change: &ChangedRange) {
+        self.span = self.span.and_then(|span| change.shift(span));
+        self.item.shift_spans(change);
+    }
+}
+
+impl<T: Annotated> Node<T> {
+    /// Returns `true` if this node has a span and the span is not affected by `change`.
+    pub fn reusable(&self, change: &ChangedRange) -> bool {
+        match self.span {
+            Some(span) => !change.intersects(span),
+            None => false,
+        }
+    }
+}
+
+/// Cache of subtrees from a previous parse which are unaffected by an edit, keyed by their starting
+/// position in the edited text.
+#[derive(Debug)]
+pub struct ReuseCache<T: Annotated> {
+    change: ChangedRange,
+    nodes: HashMap<usize, Node<T>>,
+}
+impl<T: Annotated + ShiftSpans> ReuseCache<T> {
+    /// Creates an empty cache for reuse of subtrees across the change `change`.
+    pub fn new(change: ChangedRange) -> ReuseCache<T> {
+        ReuseCache {
+            change,
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// The change this cache was created for.
+    pub fn change(&self) -> &ChangedRange { &self.change }
+
+    /// Offers a subtree from the previous parse to the cache. The subtree is stored (with spans
+    /// relocated to the edited text) if it is unaffected by the change; returns `true` if stored.
+    ///
+    /// If a subtree has already been stored at the same position, the larger of the two is kept,
+    /// so subtrees can be offered in any order. Returns `false` if the offered subtree is not kept.
+    pub fn offer(&mut self, mut node: Node<T>) -> bool {
+        if !node.reusable(&self.change) {
+            return false;
+        }
+        node.shift_spans(&self.change);
+        // reusable nodes always have a span
+        let span = node.span.unwrap();
+        let keep = match self.nodes.get(&span.start) {
+            Some(existing) => existing.span.map_or(true, |existing| existing.end < span.end),
+            None => true,
+        };
+        if keep {
+            self.nodes.insert(span.start, node);
+        }
+        keep
+    }
+
+    /// Takes the reusable subtree starting at position `pos` of the edited text, if one exists.
+    pub fn take(&mut self, pos: usize) -> Option<Node<T>> {
+        self.nodes.remove(&pos)
+    }
+
+    /// Span (in the edited text) of the reusable subtree starting at position `pos`, if one exists.
+    pub fn peek(&self, pos: usize) -> Option<Span> {
+        self.nodes.get(&pos).and_then(|node| node.span)
+    }
+
+    /// Number of subtrees in the cache.
+    pub fn len(&self) -> usize { self.nodes.len() }
+
+    /// Returns `true` if the cache contains no subtrees.
+    pub fn is_empty(&self) -> bool { self.nodes.is_empty() }
+}
@@ -4,17 +4,27 @@
 
 extern crate regex;
 #[macro_use] extern crate lazy_static;
+extern crate im_rc;
+#[cfg(feature = "nom")] extern crate nom as nom_crate;
+#[cfg(feature = "serde")] #[macro_use] extern crate serde;
 
 pub mod rules;
 pub use rules::*;
 
+pub mod span;
+pub use span::{Span, Spanned, HasSpan};
+
+pub mod source;
+pub use source::{SourceFile, SourceMap};
+pub mod incremental;
+
 pub mod node;
 pub use node::Node;
 
 pub mod scope;
 
 pub mod identifier;
-pub use identifier::Identifier;
+pub use identifier::{Identifier, SyntaxContext, IdentPath, PathSyntax, PathError};
 
 pub mod ty;
 pub use ty::{Type, FindType, Typed};
@@ -23,5 +33,6 @@ pub mod value;
 pub use value::Value;
 
 pub mod inference;
+pub mod recovery;
 pub mod log;
 pub mod operator;
@@ -25,8 +25,8 @@ impl<T: Display, O: Write, E: Write> LogListener<T, O, E> {
     pub fn new(cout: O, cerr: E) -> LogListener<T, O, E> {
         LogListener {
             messages: Vec::new(),
-            cout: cout,
-            cerr: cerr,
+            cout,
+            cerr,
         }
     }
 
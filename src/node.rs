@@ -4,6 +4,8 @@ use std::fmt::Debug;
 use std::rc::Rc;
 use std::cell::RefCell;
 
+use span::Span;
+
 /// Combination trait required for all annotation types.
 pub trait AnnotationType: Default + Debug + Clone + PartialEq {}
 impl<A: Default + Debug + Clone + PartialEq> AnnotationType for A {}
@@ -16,8 +18,11 @@ pub trait Annotated {
 
 /// Used to specify that a type has no annotation.
 #[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct EmptyAnnotation {}
 
+/// Implements `Annotated` for a type, with the specified annotation type (or `EmptyAnnotation`
+/// if none is specified).
 #[macro_export]
 macro_rules! annotate {
     ($t:tt, $anno:ty) => {
@@ -36,18 +41,33 @@ macro_rules! annotate {
 ///
 /// Adds arbitrary tree node annotation to each node.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "T: ::serde::Serialize, T::Annotation: ::serde::Serialize",
+    deserialize = "T: ::serde::Deserialize<'de>, T::Annotation: ::serde::Deserialize<'de>"
+)))]
 pub struct Node<T> where T: Annotated {
     /// Tree node item.
     pub item: T,
     /// Tree node annotation.
     pub annotation: Rc<RefCell<T::Annotation>>,
+    /// Location in the source of this node (if known).
+    pub span: Option<Span>,
 }
 impl<T: Annotated> Node<T> {
     /// Creates a new tree node with default annotation.
     pub fn new(item: T) -> Node<T> {
         Node {
-            item: item,
+            item,
             annotation: Rc::new(RefCell::new(T::Annotation::default())),
+            span: None,
+        }
+    }
+    /// Creates a new tree node with default annotation, located at `span` in the source.
+    pub fn with_span(item: T, span: Span) -> Node<T> {
+        Node {
+            span: Some(span),
+            ..Node::new(item)
         }
     }
 }
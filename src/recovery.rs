@@ -0,0 +1,139 @@
+//! Helpers for recovering from syntax errors during parsing.
+//!
+//! Rather than aborting at the first syntax error, a parser can record the error, skip ahead to a
+//! synchronisation token (e.g. `;` or `}`), substitute an error node for the unparseable region,
+//! and continue. The recorded errors can then be reported together through a `LogListener`.
+
+use std::fmt;
+use std::io::Write;
+
+use log::LogListener;
+use node::{Annotated, EmptyAnnotation, Node};
+use rules::RuleResult;
+use span::Span;
+
+/// Syntax error encountered while parsing, along with the span of source it covers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// Error message.
+    pub message: String,
+    /// Location of the error in the source.
+    pub span: Span,
+}
+impl ParseError {
+    /// Creates a new parse error.
+    pub fn new<S: Into<String>>(message: S, span: Span) -> ParseError {
+        ParseError { message: message.into(), span }
+    }
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        write!(f, "syntax error at {}: {}", self.span, self.message)
+    }
+}
+
+/// Abstract syntax tree node standing in for a region of source that failed to parse.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorNode {
+    /// The error that caused this node to be created.
+    pub error: ParseError,
+}
+impl Annotated for ErrorNode { type Annotation = EmptyAnnotation; }
+
+/// Trait for syntax tree types which can be constructed in place of a region that failed to
+/// parse. Typically implemented by an AST enum with a variant containing an `ErrorNode`.
+pub trait Recover: Sized {
+    /// Creates an error item from a parse error.
+    fn from_error(error: ParseError) -> Self;
+}
+impl Recover for ErrorNode {
+    fn from_error(error: ParseError) -> ErrorNode { ErrorNode { error } }
+}
+impl<T: Annotated + Recover> Recover for Node<T> {
+    fn from_error(error: ParseError) -> Node<T> { Node::new(T::from_error(error)) }
+}
+
+/// Returns the position of the first synchronisation token in `input` at or after `pos`, or the
+/// end of the input if no synchronisation token is found.
+pub fn skip_until(input: &str, pos: usize, sync: &[&str]) -> usize {
+    let rest = &input[pos..];
+    sync.iter()
+        .filter_map(|token| rest.find(token))
+        .min()
+        .map_or(input.len(), |offset| pos + offset)
+}
+
+/// Returns the position immediately following the first synchronisation token in `input` at or
+/// after `pos`, or the end of the input if no synchronisation token is found.
+pub fn skip_past(input: &str, pos: usize, sync: &[&str]) -> usize {
+    let rest = &input[pos..];
+    sync.iter()
+        .filter_map(|token| rest.find(token).map(|offset| (offset, token.len())))
+        .min()
+        .map_or(input.len(), |(offset, len)| pos + offset + len)
+}
+
+/// Accumulator for syntax errors encountered during a parse.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ParseErrors {
+    errors: Vec<ParseError>,
+}
+impl ParseErrors {
+    /// Creates a new, empty error accumulator.
+    pub fn new() -> ParseErrors { ParseErrors::default() }
+
+    /// Records a parse error.
+    pub fn push(&mut self, error: ParseError) { self.errors.push(error); }
+
+    /// Returns `true` if no errors have been recorded.
+    pub fn is_empty(&self) -> bool { self.errors.is_empty() }
+
+    /// Number of errors recorded.
+    pub fn len(&self) -> usize { self.errors.len() }
+
+    /// Iterates over the recorded errors, in the order they were recorded.
+    pub fn iter(&self) -> ::std::slice::Iter<'_, ParseError> { self.errors.iter() }
+
+    /// Applies `rule` at position `pos`. On failure, records the error, skips to the next
+    /// synchronisation token, and returns an error item covering the skipped region in place of
+    /// the rule's result.
+    ///
+    /// Recovery always makes progress (unless `pos` is at the end of the input): if a
+    /// synchronisation token is found at `pos` itself, the token is consumed as part of the
+    /// skipped region.
+    pub fn recover<T, F>(&mut self, input: &str, pos: usize, sync: &[&str], rule: F) -> (usize, T)
+        where F: Fn(&str, usize) -> RuleResult<T>, T: Recover
+    {
+        match rule(input, pos) {
+            Ok(result) => result,
+            Err(msg) => {
+                let mut end = skip_until(input, pos, sync);
+                if end == pos && pos < input.len() {
+                    end = skip_past(input, pos, sync);
+                }
+                if end == pos && pos < input.len() {
+                    // only empty synchronisation tokens matched; skip a single character
+                    end += input[pos..].chars().next().map_or(0, char::len_utf8);
+                }
+                let error = ParseError::new(msg, Span::new(pos, end));
+                self.push(error.clone());
+                (end, T::from_error(error))
+            }
+        }
+    }
+
+    /// Sends all recorded errors to a log listener as error-level messages, emptying this
+    /// accumulator.
+    pub fn log_to<T, O, E>(&mut self, listener: &mut LogListener<T, O, E>)
+        where T: fmt::Display + From<ParseError>, O: Write, E: Write
+    {
+        for error in self.errors.drain(..) {
+            listener.error(T::from(error));
+        }
+    }
+}
+impl IntoIterator for ParseErrors {
+    type Item = ParseError;
+    type IntoIter = ::std::vec::IntoIter<ParseError>;
+    fn into_iter(self) -> Self::IntoIter { self.errors.into_iter() }
+}
@@ -0,0 +1,98 @@
+//! Parser combinator adapters for the literal rules.
+//!
+//! Each adapter wraps a rule into a nom-style combinator: a function taking the remaining input
+//! `&str` and returning the input left after the match along with the parsed value. Since
+//! combinators only see the remaining input, adapters are constructed with the full source
+//! string, which is used to compute the `Span` of each match.
+//!
+//! With the `nom` feature enabled, the `nom` submodule provides adapters returning
+//! `nom::IResult`.
+
+use rules::{int, float, string, RuleResult};
+use span::{Span, Spanned};
+
+/// Result of applying a combinator: on success, the remaining input and the spanned value.
+pub type CombinatorResult<'a, T> = Result<(&'a str, Spanned<T>), &'static str>;
+
+/// Computes the byte offset of `input` within `source`, if `input` is a slice of `source`.
+fn offset_in(source: &str, input: &str) -> Option<usize> {
+    let start = source.as_ptr() as usize;
+    let pos = input.as_ptr() as usize;
+    if pos >= start && pos + input.len() <= start + source.len() {
+        Some(pos - start)
+    } else {
+        None
+    }
+}
+
+/// Adapts a rule into a combinator over slices of `source`. Spans of parsed values are relative to
+/// the start of `source`.
+///
+/// # Failures
+/// The combinator fails if the rule fails, or if it is applied to a string that is not a slice of
+/// `source`.
+pub fn adapt<'a, T, F>(source: &'a str, rule: F) -> impl Fn(&'a str) -> CombinatorResult<'a, T>
+    where F: Fn(&str, usize) -> RuleResult<T>
+{
+    move |input: &'a str| {
+        let offset = offset_in(source, input).ok_or("input is not a slice of source")?;
+        let (end, item) = rule(input, 0)?;
+        Ok((&input[end..], Spanned::new(item, Span::new(offset, offset + end))))
+    }
+}
+
+/// Integer literal combinator over slices of `source`.
+pub fn int_lit<'a>(source: &'a str) -> impl Fn(&'a str) -> CombinatorResult<'a, i64> {
+    adapt(source, int::int_ext)
+}
+
+/// Floating point literal combinator over slices of `source`.
+pub fn float_lit<'a>(source: &'a str) -> impl Fn(&'a str) -> CombinatorResult<'a, f64> {
+    adapt(source, float::float_ext)
+}
+
+/// String literal combinator over slices of `source`.
+pub fn string_lit<'a>(source: &'a str) -> impl Fn(&'a str) -> CombinatorResult<'a, String> {
+    adapt(source, string::string_ext)
+}
+
+#[cfg(feature = "nom")]
+pub mod nom {
+    //! Adapters producing `nom` parsers.
+    //!
+    //! Rule failures are reported as recoverable `nom::Err::Error`s with `ErrorKind::Verify`.
+
+    use nom_crate::{IResult, Err};
+    use nom_crate::error::{Error, ErrorKind};
+
+    use rules::RuleResult;
+    use span::Spanned;
+
+    /// Adapts a rule into a `nom` parser over slices of `source`.
+    pub fn adapt<'a, T, F>(source: &'a str, rule: F)
+        -> impl Fn(&'a str) -> IResult<&'a str, Spanned<T>>
+        where F: Fn(&str, usize) -> RuleResult<T>
+    {
+        let combinator = super::adapt(source, rule);
+        move |input: &'a str| {
+            combinator(input).map_err(|_| Err::Error(Error::new(input, ErrorKind::Verify)))
+        }
+    }
+
+    /// Integer literal `nom` parser over slices of `source`.
+    pub fn int_lit<'a>(source: &'a str) -> impl Fn(&'a str) -> IResult<&'a str, Spanned<i64>> {
+        adapt(source, ::rules::int::int_ext)
+    }
+
+    /// Floating point literal `nom` parser over slices of `source`.
+    pub fn float_lit<'a>(source: &'a str) -> impl Fn(&'a str) -> IResult<&'a str, Spanned<f64>> {
+        adapt(source, ::rules::float::float_ext)
+    }
+
+    /// String literal `nom` parser over slices of `source`.
+    pub fn string_lit<'a>(source: &'a str)
+        -> impl Fn(&'a str) -> IResult<&'a str, Spanned<String>>
+    {
+        adapt(source, ::rules::string::string_ext)
+    }
+}
@@ -0,0 +1,34 @@
+//! Floating point parsing rule.
+//!
+//! Parses a Rust-style floating point number, with optional exponent.
+
+use regex::Regex;
+
+use rules::{remove_underscores, parse_all, RuleResult};
+
+lazy_static! {
+    /// Regular expression for a front-anchored floating point literal.
+    pub static ref FLOAT_REGEX_ANC: Regex = {
+        Regex::new(r"(?x)
+            ^(?:
+            (?:[_0-9]+\.(?:[_0-9]+(?:[eE][\+-]?[_0-9]+)?)?)
+            |
+            (?:[_0-9]+[eE][\+-]?[_0-9]+)
+            )
+        ").unwrap()
+    };
+}
+
+/// Interface method for parsers to match and parse a floating point number starting at position
+/// `pos` in `input`.
+pub fn float_ext(input: &str, pos: usize) -> RuleResult<f64> {
+    let number = FLOAT_REGEX_ANC.find(&input[pos..]).ok_or("expected floating point number")?;
+    let value = remove_underscores(number.as_str()).parse::<f64>()
+        .map_err(|_| "unable to parse f64")?;
+    Ok((pos + number.end(), value))
+}
+
+/// Parses an entire input string as a floating point number.
+pub fn float(input: &str) -> Result<f64, &'static str> {
+    parse_all(input, float_ext)
+}
@@ -0,0 +1,53 @@
+//! Integer parsing rule.
+//!
+//! Parses an Rust-style integer specified in either decimal, binary (e.g. 0b0101),
+//! octal (e.g. 0o754), or hexadecimal (e.g. 0x1AF3) format.
+
+use regex::Regex;
+
+use rules::{remove_underscores, parse_all, RuleResult};
+
+lazy_static! {
+    /// Regular expression for a front-anchored integer literal.
+    pub static ref INT_REGEX_ANC: Regex = {
+        Regex::new(r"(?x)
+            ^(?:
+            0x(?P<hex>[_0-9A-Fa-f]+)   # hexadecimal
+            |                          # or
+            0o(?P<oct>[_0-8]+)         # octal
+            |                          # or
+            0b(?P<bin>[_0-1]+)         # binary
+            |                          # or
+            (?P<dec>[0-9][_0-9]*)      # decimal
+            )
+        ").unwrap()
+    };
+}
+
+/// Interface method for parsers to match and parse an integer starting at position `pos` in
+/// `input`.
+pub fn int_ext(input: &str, pos: usize) -> RuleResult<i64> {
+    let number = INT_REGEX_ANC.captures(&input[pos..]).ok_or("expected integer")?;
+    let end = pos + number.get(0).unwrap().end();
+    let value = if let Some(mat) = number.name("dec") {
+        remove_underscores(mat.as_str()).parse::<i64>()
+            .map_err(|_| "unable to parse decimal integer")
+    } else if let Some(mat) = number.name("hex") {
+        i64::from_str_radix(remove_underscores(mat.as_str()).as_str(), 16)
+            .map_err(|_| "unable to parse hexadecimal integer")
+    } else if let Some(mat) = number.name("bin") {
+        i64::from_str_radix(remove_underscores(mat.as_str()).as_str(), 2)
+            .map_err(|_| "unable to parse binary integer")
+    } else if let Some(mat) = number.name("oct") {
+        i64::from_str_radix(remove_underscores(mat.as_str()).as_str(), 8)
+            .map_err(|_| "unable to parse octal integer")
+    } else {
+        Err("unable to parse integer")
+    }?;
+    Ok((end, value))
+}
+
+/// Parses an entire input string as an integer.
+pub fn int(input: &str) -> Result<i64, &'static str> {
+    parse_all(input, int_ext)
+}
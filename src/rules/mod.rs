@@ -1,25 +1,18 @@
-//! Collection of rules to use with `rustpeg` parser specification files.
+//! Collection of literal-parsing rules.
+//!
+//! Each rule is exposed as a plain Rust function with the signature
+//! `fn(&str, usize) -> RuleResult<T>`: given the input and a starting byte position, it returns
+//! the position immediately following the match along with the parsed value. This allows the
+//! rules to be called from any parser generator or hand-written parser.
 
-pub mod int {
-    //! Integer parsing rule.
-    //!
-    //! Parses an Rust-style integer specified in either decimal, binary (e.g. 0b0101),
-    //! octal (e.g. 0o754), or hexadecimal (e.g. 0x1AF3) format.
-
-    // allow missing docs in generated code
-    #![allow(missing_docs)]
-    include!(concat!(env!("OUT_DIR"), "/int.rs"));
-}
-pub mod float {
-    //! Floating point parsing rule.
-    //!
-    //! Parses a Rust-style floating point number, with optional exponent.
-
-    // allow missing docs in generated code
-    #![allow(missing_docs)]
-    include!(concat!(env!("OUT_DIR"), "/float.rs"));
-}
+pub mod int;
+pub mod float;
 pub mod string;
+pub mod combinator;
+
+/// Result of applying a rule at a position in an input string: on success, the position
+/// following the match and the parsed value.
+pub type RuleResult<T> = ::std::result::Result<(usize, T), &'static str>;
 
 /// Removes underscores from a string. Used in number parsing (where underscores are allowed
 /// as grouping symbols but do not affect the parsed value).
@@ -28,3 +21,16 @@ pub fn remove_underscores(input: &str) -> String {
     for c in input.chars().filter(|&c| c != '_') { s.push(c); }
     s
 }
+
+/// Applies a rule to an entire input string, failing if the rule does not consume all of the
+/// input.
+pub fn parse_all<T, F>(input: &str, rule: F) -> ::std::result::Result<T, &'static str>
+    where F: Fn(&str, usize) -> RuleResult<T>
+{
+    let (pos, value) = rule(input, 0)?;
+    if pos == input.len() {
+        Ok(value)
+    } else {
+        Err("unexpected trailing input")
+    }
+}
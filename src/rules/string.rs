@@ -3,6 +3,8 @@
 use regex::{Regex, Captures};
 use std::char;
 
+use rules::{parse_all, RuleResult};
+
 type Result<T> = ::std::result::Result<T, &'static str>;
 
 /// Generates a regex pattern 1-6 hexadecimal digit unicode character specification.
@@ -11,7 +13,7 @@ macro_rules! unicode_char_pattern {
 }
 lazy_static! {
     /// Regular expression for unicode character specification.
-    pub static ref UNICODE_REGEX: Regex = { Regex::new(unicode_char_pattern!()).unwrap() };
+    pub static ref UNICODE_REGEX: Regex = Regex::new(unicode_char_pattern!()).unwrap();
 }
 
 /// Generates a regex pattern for escape sequences: single-caracter, one-byte, or unicode.
@@ -28,7 +30,7 @@ macro_rules! escape_pattern {
 }
 lazy_static! {
     /// Regular expression for string escapes.
-    pub static ref ESCAPE_REGEX: Regex = { Regex::new(escape_pattern!()).unwrap() };
+    pub static ref ESCAPE_REGEX: Regex = Regex::new(escape_pattern!()).unwrap();
 }
 
 /// Regex patten for a string literal.
@@ -51,7 +53,7 @@ pub const PTN_STRING: &str = concat!(
 );
 lazy_static! {
     /// Regular expression for a string literal.
-    pub static ref STRING_REGEX: Regex = { Regex::new(PTN_STRING).unwrap() };
+    pub static ref STRING_REGEX: Regex = Regex::new(PTN_STRING).unwrap();
 }
 lazy_static! {
     /// Regular expression for a front-anchored string literal.
@@ -61,12 +63,24 @@ lazy_static! {
     };
 }
 
-/// Interface method for `rustpeg` files to use the string regex.
-pub fn match_str_ext(input: &str, pos: usize) -> Option<(usize, Captures)> {
-    match STRING_REGEX_ANC.captures(&input[pos..]) {
-        Some(caps) => Some((pos + caps.get(0).unwrap().as_str().len(), caps)),
-        None => None
-    }
+/// Matches the string regex at position `pos` in `input`, returning the position following the
+/// match and the regex captures. Like the other rules, this is a plain function which can be
+/// called from any parser (see `convert_string` for processing the captures).
+pub fn match_str_ext(input: &str, pos: usize) -> Option<(usize, Captures<'_>)> {
+    STRING_REGEX_ANC.captures(&input[pos..])
+        .map(|caps| (pos + caps.get(0).unwrap().as_str().len(), caps))
+}
+
+/// Interface method for parsers to match a string literal starting at position `pos` in `input`,
+/// returning the literal contents with all escapes processed.
+pub fn string_ext(input: &str, pos: usize) -> RuleResult<String> {
+    let (end, caps) = match_str_ext(input, pos).ok_or("expected string literal")?;
+    Ok((end, convert_string(caps)?))
+}
+
+/// Parses an entire input string as a string literal.
+pub fn string(input: &str) -> Result<String> {
+    parse_all(input, string_ext)
 }
 
 /// Processes regular expression captures from `STRING_REGEX` or `STRING_REGEX_ANC` into `String`,
@@ -112,11 +126,11 @@ fn escape(code: &str) -> Result<String> {
         "\\0"     => Ok("\0".to_string()),
         "\\'"     => Ok("'".to_string()),
         "\\\""    => Ok("\"".to_string()),
-        "\\x"     => Ok(two_digit_escape(&code[2..])?),
+        "\\x"     => two_digit_escape(&code[2..]),
         "\\u"     => {
             if let Some(caps) = UNICODE_REGEX.captures(code) {
                 if let Some(mat) = caps.get(1) {
-                    return Ok(unicode_escape(mat.as_str())?);
+                    return unicode_escape(mat.as_str());
                 }
             }
             Err("improperly formatted unicode character code")
@@ -0,0 +1,177 @@
+//! Frame-based call stack implementation.
+//!
+//! Unlike `MemoryScope`, which ties storage to lexical scopes, a `CallStack` holds run-time
+//! storage in activation frames: each function call pushes a new frame with its own locals, so
+//! recursive calls do not share (or need to clone) storage. Locals are indexed by slot within a
+//! single flat slot space per frame.
+//!
+//! The slots assigned by a `SlotTable` during analysis (see `SymbolLocation`) can be used directly
+//! only for symbols defined in the function's own scope. Each nested block scope has its own
+//! `SlotTable`, whose slots restart at 0, so a compiler must map block slots to distinct frame
+//! slots itself (e.g. by offsetting each block's slots past the slots already allocated in the
+//! frame when the block is entered) and size the frame accordingly.
+
+use std::fmt::{self, Display};
+
+use Value;
+
+/// Default maximum depth of a `CallStack`.
+pub const DEFAULT_MAX_DEPTH: usize = 1024;
+
+/// Error resulting from a failed call stack operation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallError {
+    /// A call was made when the stack had already reached its maximum depth.
+    StackOverflow {
+        /// Maximum depth of the stack.
+        max_depth: usize,
+    },
+    /// A return (or frame access) was attempted when the stack had no frames.
+    EmptyStack,
+    /// The slot index is out of range for the frame.
+    InvalidSlot(usize),
+    /// The slot has not been assigned a value.
+    Uninitialised(usize),
+}
+impl Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        match *self {
+            CallError::StackOverflow { max_depth } => {
+                write!(f, "stack overflow: maximum call depth of {} exceeded", max_depth)
+            },
+            CallError::EmptyStack => write!(f, "call stack is empty"),
+            CallError::InvalidSlot(slot) => write!(f, "invalid local slot: {}", slot),
+            CallError::Uninitialised(slot) => {
+                write!(f, "use of uninitialised local slot: {}", slot)
+            },
+        }
+    }
+}
+
+/// Activation frame for a single function call, holding slot-indexed locals (values of type
+/// `Val`), the address to return to, and arbitrary per-frame metadata of type `Meta`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame<Val, Meta = ()> {
+    locals: Vec<Option<Val>>,
+    /// Address to return to when this frame is popped (e.g. a node or instruction index), if any.
+    pub return_address: Option<usize>,
+    /// Per-frame metadata (e.g. the function name, or the call site span).
+    pub meta: Meta,
+}
+impl<Val: Value, Meta> Frame<Val, Meta> {
+    /// Creates a new frame with `num_locals` unassigned local slots.
+    pub fn new(num_locals: usize, return_address: Option<usize>, meta: Meta) -> Frame<Val, Meta> {
+        Frame {
+            locals: vec![None; num_locals],
+            return_address,
+            meta,
+        }
+    }
+    /// Number of local slots in this frame.
+    pub fn len(&self) -> usize { self.locals.len() }
+    /// Returns `true` if this frame has no local slots.
+    pub fn is_empty(&self) -> bool { self.locals.is_empty() }
+    /// Retrieves a reference to the value of a local slot.
+    ///
+    /// # Failures
+    /// Fails with `CallError::InvalidSlot` if the slot is out of range, or
+    /// `CallError::Uninitialised` if the slot has not been assigned.
+    pub fn get(&self, slot: usize) -> Result<&Val, CallError> {
+        match self.locals.get(slot) {
+            Some(Some(value)) => Ok(value),
+            Some(None) => Err(CallError::Uninitialised(slot)),
+            None => Err(CallError::InvalidSlot(slot)),
+        }
+    }
+    /// Sets the value of a local slot, returning the previous value (if any).
+    ///
+    /// # Failures
+    /// Fails with `CallError::InvalidSlot` if the slot is out of range.
+    pub fn set(&mut self, slot: usize, value: Val) -> Result<Option<Val>, CallError> {
+        match self.locals.get_mut(slot) {
+            Some(local) => Ok(local.replace(value)),
+            None => Err(CallError::InvalidSlot(slot)),
+        }
+    }
+    /// Iterator over the local slots of this frame, in slot order.
+    pub fn locals(&self) -> ::std::slice::Iter<'_, Option<Val>> {
+        self.locals.iter()
+    }
+}
+
+/// Stack of activation frames, with a maximum depth.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallStack<Val, Meta = ()> {
+    frames: Vec<Frame<Val, Meta>>,
+    max_depth: usize,
+}
+impl<Val: Value, Meta> Default for CallStack<Val, Meta> {
+    fn default() -> CallStack<Val, Meta> {
+        CallStack::with_max_depth(DEFAULT_MAX_DEPTH)
+    }
+}
+impl<Val: Value, Meta> CallStack<Val, Meta> {
+    /// Creates a new, empty call stack with the default maximum depth (`DEFAULT_MAX_DEPTH`).
+    pub fn new() -> CallStack<Val, Meta> { CallStack::default() }
+    /// Creates a new, empty call stack with the specified maximum depth.
+    pub fn with_max_depth(max_depth: usize) -> CallStack<Val, Meta> {
+        CallStack {
+            frames: vec![],
+            max_depth,
+        }
+    }
+    /// Maximum depth of this stack.
+    pub fn max_depth(&self) -> usize { self.max_depth }
+    /// Current depth (number of frames) of this stack.
+    pub fn depth(&self) -> usize { self.frames.len() }
+    /// Pushes a new frame with `num_locals` unassigned local slots, returning a mutable reference
+    /// to the new frame (e.g. for assigning arguments).
+    ///
+    /// # Failures
+    /// Fails with `CallError::StackOverflow` if the stack has reached its maximum depth.
+    pub fn call(&mut self, num_locals: usize, return_address: Option<usize>, meta: Meta)
+        -> Result<&mut Frame<Val, Meta>, CallError>
+    {
+        if self.frames.len() >= self.max_depth {
+            return Err(CallError::StackOverflow { max_depth: self.max_depth });
+        }
+        self.frames.push(Frame::new(num_locals, return_address, meta));
+        Ok(self.frames.last_mut().unwrap())
+    }
+    /// Pops the current frame off the stack, returning it (so the caller can retrieve the return
+    /// address).
+    ///
+    /// # Failures
+    /// Fails with `CallError::EmptyStack` if there are no frames on the stack.
+    pub fn ret(&mut self) -> Result<Frame<Val, Meta>, CallError> {
+        self.frames.pop().ok_or(CallError::EmptyStack)
+    }
+    /// Accesses the current (topmost) frame, if exists.
+    pub fn current(&self) -> Option<&Frame<Val, Meta>> { self.frames.last() }
+    /// Mutably accesses the current (topmost) frame, if exists.
+    pub fn current_mut(&mut self) -> Option<&mut Frame<Val, Meta>> { self.frames.last_mut() }
+    /// Accesses the frame `depth` frames below the current frame (with the current frame at depth
+    /// 0), if exists.
+    pub fn frame(&self, depth: usize) -> Option<&Frame<Val, Meta>> {
+        self.frames.len().checked_sub(depth + 1).map(|index| &self.frames[index])
+    }
+    /// Retrieves a reference to the value of a local slot in the current frame.
+    ///
+    /// # Failures
+    /// Fails with `CallError::EmptyStack` if there are no frames on the stack, or as `Frame::get`.
+    pub fn get(&self, slot: usize) -> Result<&Val, CallError> {
+        self.current().ok_or(CallError::EmptyStack)?.get(slot)
+    }
+    /// Sets the value of a local slot in the current frame, returning the previous value (if
+    /// any).
+    ///
+    /// # Failures
+    /// Fails with `CallError::EmptyStack` if there are no frames on the stack, or as `Frame::set`.
+    pub fn set(&mut self, slot: usize, value: Val) -> Result<Option<Val>, CallError> {
+        self.current_mut().ok_or(CallError::EmptyStack)?.set(slot, value)
+    }
+    /// Iterator over the frames of this stack, from the current frame to the bottom of the stack.
+    pub fn frames(&self) -> ::std::iter::Rev<::std::slice::Iter<'_, Frame<Val, Meta>>> {
+        self.frames.iter().rev()
+    }
+}
@@ -0,0 +1,138 @@
+//! Closure capture analysis.
+//!
+//! Scopes marked as function scopes (`ScopeKind::Function`) act as capture boundaries: when a
+//! symbol is resolved (through `CaptureStore`) from within a function scope, but is defined
+//! outside of it, the function scope records the symbol as captured, along with whether it was
+//! read or written. Captures are recorded at every function boundary crossed, so a function
+//! nested within another records symbols captured by the inner function as its own captures.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use scope::{Scope, MemberStore, ResolveError};
+use scope::symbol::{SymbolTable, resolve_outward};
+use Identifier;
+
+/// Kind of a scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScopeKind {
+    /// Block scope (the default).
+    #[default]
+    Block,
+    /// Function body scope, which acts as a boundary for capture analysis.
+    Function,
+}
+
+/// Manner in which a captured symbol is accessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Access {
+    /// Symbol is only read.
+    Read,
+    /// Symbol is only written.
+    Write,
+    /// Symbol is both read and written.
+    ReadWrite,
+}
+impl Access {
+    /// Combines two accesses of the same symbol.
+    pub fn merge(self, other: Access) -> Access {
+        if self == other { self } else { Access::ReadWrite }
+    }
+    /// Returns `true` if this access includes a read.
+    pub fn reads(self) -> bool { self != Access::Write }
+    /// Returns `true` if this access includes a write.
+    pub fn writes(self) -> bool { self != Access::Read }
+}
+
+/// Symbol captured by a function scope.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Capture {
+    /// Identifier of the captured symbol.
+    pub ident: Identifier,
+    /// Accumulated access of the captured symbol within the function scope.
+    pub access: Access,
+}
+
+impl<T> Scope<T> {
+    /// Returns `true` if this scope is a function scope.
+    pub fn is_function(&self) -> bool { self.kind == ScopeKind::Function }
+
+    /// Records a capture of an outer symbol in this scope, merging with any previously recorded
+    /// access of the same symbol.
+    pub fn record_capture(&mut self, ident: &Identifier, access: Access) {
+        match self.captures.iter_mut().find(|capture| &capture.ident == ident) {
+            Some(capture) => { capture.access = capture.access.merge(access); },
+            None => self.captures.push(Capture { ident: *ident, access }),
+        }
+    }
+
+    /// Recorded access for a captured symbol, if this scope captures it.
+    pub fn captured(&self, ident: &Identifier) -> Option<Access> {
+        self.captures.iter().find(|capture| &capture.ident == ident).map(|capture| capture.access)
+    }
+}
+
+/// Methods for resolving symbols while recording captures across function scopes.
+///
+/// These methods are implemented on `Rc<RefCell<Scope<T>>>`, since recording a capture requires
+/// mutable access to scopes along the parent chain. No scope along the chain may be borrowed
+/// while calling these methods.
+pub trait CaptureStore<Sym> {
+    /// Resolves a symbol, recording it as captured with access `access` in every function scope
+    /// crossed between this scope and the scope providing the symbol.
+    fn resolve_capture(&self, ident: &Identifier, access: Access) -> Option<Sym>;
+    /// Resolves a symbol which is being read, recording captures as necessary.
+    fn resolve_read(&self, ident: &Identifier) -> Option<Sym> {
+        self.resolve_capture(ident, Access::Read)
+    }
+    /// Resolves a symbol which is being written, recording captures as necessary.
+    fn resolve_write(&self, ident: &Identifier) -> Option<Sym> {
+        self.resolve_capture(ident, Access::Write)
+    }
+}
+impl<Sym: Clone, T> CaptureStore<Sym> for Rc<RefCell<Scope<T>>> where T: SymbolTable<Sym> {
+    fn resolve_capture(&self, ident: &Identifier, access: Access) -> Option<Sym> {
+        // captures are recorded under the identifier of the binding, once marks are stripped
+        resolve_outward(ident, |candidate| resolve_capture_exact(self, candidate, access)).ok()
+    }
+}
+
+fn resolve_capture_exact<Sym: Clone, T>(scope: &Rc<RefCell<Scope<T>>>, ident: &Identifier,
+    access: Access) -> Result<Sym, ResolveError>
+    where T: SymbolTable<Sym>
+{
+    let mut crossed = vec![];
+    let mut current = Rc::clone(scope);
+    let symbol = loop {
+        let parent = {
+            let scope = current.borrow();
+            let found = match scope.resolve_member(ident) {
+                Some(symbol) => Ok(Some(symbol)),
+                None => scope.resolve_import(ident),
+            };
+            match found {
+                Ok(Some(symbol)) => break Ok(symbol),
+                Ok(None) => {},
+                // ambiguous imports fail the resolution
+                Err(error) => break Err(error),
+            }
+            match scope.parent {
+                Some(ref parent) => Rc::clone(parent),
+                None => break Err(ResolveError::Undefined(*ident)),
+            }
+        };
+        if current.borrow().is_function() {
+            crossed.push(current);
+        }
+        current = parent;
+    };
+    if symbol.is_ok() {
+        for scope in crossed {
+            scope.borrow_mut().record_capture(ident, access);
+        }
+    }
+    symbol
+}
@@ -0,0 +1,196 @@
+//! Garbage-collected heap implementation.
+//!
+//! A `Heap` stores values which are referenced indirectly through `Handle`s, allowing values such
+//! as lists, objects and closures to be shared (and to form cycles) without copying. Unreachable
+//! values are reclaimed by a mark-and-sweep collector: values reachable from a set of roots (e.g.
+//! a `MemoryScope` or a `CallStack`) are kept, and everything else is freed. Value types describe
+//! which handles they hold by implementing `Trace`.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use scope::{Scope, SlotTable, SymbolMemory, CallStack, Frame};
+use Value;
+
+/// Reference to a value stored in a `Heap`.
+///
+/// Handles are cheap to copy. A handle to a value which has since been collected is never reused
+/// for a later allocation: accessing the heap with such a handle returns `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: usize,
+}
+
+/// Trait for types which may hold handles to heap values, allowing the garbage collector to find
+/// all reachable values.
+pub trait Trace {
+    /// Pushes every handle directly held by this object onto `handles`. Handles held indirectly
+    /// (through other heap values) do not need to be included.
+    fn trace(&self, handles: &mut Vec<Handle>);
+}
+impl Trace for Handle {
+    fn trace(&self, handles: &mut Vec<Handle>) {
+        handles.push(*self);
+    }
+}
+impl<T: Trace + ?Sized> Trace for &T {
+    fn trace(&self, handles: &mut Vec<Handle>) {
+        (**self).trace(handles);
+    }
+}
+impl<T: Trace> Trace for Option<T> {
+    fn trace(&self, handles: &mut Vec<Handle>) {
+        if let Some(ref item) = *self {
+            item.trace(handles);
+        }
+    }
+}
+impl<T: Trace> Trace for Vec<T> {
+    fn trace(&self, handles: &mut Vec<Handle>) {
+        for item in self {
+            item.trace(handles);
+        }
+    }
+}
+impl<T: Trace> Trace for Rc<RefCell<T>> {
+    fn trace(&self, handles: &mut Vec<Handle>) {
+        self.borrow().trace(handles);
+    }
+}
+impl<Sym, Val: Trace> Trace for SymbolMemory<Sym, Val> {
+    fn trace(&self, handles: &mut Vec<Handle>) {
+        self.memory().trace(handles);
+    }
+}
+impl<V: Trace> Trace for SlotTable<V> {
+    fn trace(&self, handles: &mut Vec<Handle>) {
+        for (_, value) in self.iter() {
+            value.trace(handles);
+        }
+    }
+}
+impl<K, V: Trace> Trace for HashMap<K, V> {
+    fn trace(&self, handles: &mut Vec<Handle>) {
+        for value in self.values() {
+            value.trace(handles);
+        }
+    }
+}
+/// Traces the values in this scope and all its ancestor and base scopes.
+impl<T: Trace> Trace for Scope<T> {
+    fn trace(&self, handles: &mut Vec<Handle>) {
+        self.item.trace(handles);
+        self.bases.trace(handles);
+        self.parent.trace(handles);
+    }
+}
+impl<Val: Value + Trace, Meta> Trace for Frame<Val, Meta> {
+    fn trace(&self, handles: &mut Vec<Handle>) {
+        for local in self.locals() {
+            local.trace(handles);
+        }
+    }
+}
+impl<Val: Value + Trace, Meta> Trace for CallStack<Val, Meta> {
+    fn trace(&self, handles: &mut Vec<Handle>) {
+        for frame in self.frames() {
+            frame.trace(handles);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct HeapSlot<V> {
+    value: Option<V>,
+    generation: usize,
+    marked: bool,
+}
+
+/// Heap of values of type `V`, with mark-and-sweep garbage collection.
+#[derive(Debug, Clone)]
+pub struct Heap<V> {
+    slots: Vec<HeapSlot<V>>,
+    free: Vec<usize>,
+    len: usize,
+}
+impl<V> Default for Heap<V> {
+    fn default() -> Heap<V> {
+        Heap {
+            slots: vec![],
+            free: vec![],
+            len: 0,
+        }
+    }
+}
+impl<V: Trace> Heap<V> {
+    /// Creates a new, empty heap.
+    pub fn new() -> Heap<V> { Heap::default() }
+    /// Allocates a value on the heap, returning its handle.
+    pub fn alloc(&mut self, value: V) -> Handle {
+        self.len += 1;
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.value = Some(value);
+                Handle { index, generation: slot.generation }
+            },
+            None => {
+                self.slots.push(HeapSlot { value: Some(value), generation: 0, marked: false });
+                Handle { index: self.slots.len() - 1, generation: 0 }
+            }
+        }
+    }
+    fn slot(&self, handle: Handle) -> Option<&HeapSlot<V>> {
+        self.slots.get(handle.index)
+            .filter(|slot| slot.generation == handle.generation && slot.value.is_some())
+    }
+    /// Accesses the value for a handle, if it is still live.
+    pub fn get(&self, handle: Handle) -> Option<&V> {
+        self.slot(handle).and_then(|slot| slot.value.as_ref())
+    }
+    /// Mutably accesses the value for a handle, if it is still live.
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut V> {
+        match self.slots.get_mut(handle.index) {
+            Some(slot) if slot.generation == handle.generation => slot.value.as_mut(),
+            _ => None
+        }
+    }
+    /// Returns `true` if the handle refers to a live value in this heap.
+    pub fn contains(&self, handle: Handle) -> bool { self.slot(handle).is_some() }
+    /// Number of live values in this heap.
+    pub fn len(&self) -> usize { self.len }
+    /// Returns `true` if there are no live values in this heap.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+    /// Collects garbage: frees every value not reachable from `roots` (directly, or through other
+    /// heap values). Returns the number of values freed.
+    pub fn collect(&mut self, roots: &[&dyn Trace]) -> usize {
+        // mark
+        let mut pending = vec![];
+        for root in roots {
+            root.trace(&mut pending);
+        }
+        while let Some(handle) = pending.pop() {
+            if !self.contains(handle) || self.slots[handle.index].marked {
+                continue;
+            }
+            let slot = &mut self.slots[handle.index];
+            slot.marked = true;
+            slot.value.trace(&mut pending);
+        }
+        // sweep
+        let mut freed = 0;
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.marked {
+                slot.marked = false;
+            } else if slot.value.take().is_some() {
+                slot.generation += 1;
+                self.free.push(index);
+                freed += 1;
+            }
+        }
+        self.len -= freed;
+        freed
+    }
+}
@@ -0,0 +1,148 @@
+//! Namespace imports.
+//!
+//! Namespaces (modules) are represented as scopes attached to namespace symbols through the
+//! `Scoped` trait, so qualified names such as `std::io::Write` can be resolved with
+//! `MemberStore::resolve_path`. Imports bring names from a namespace scope into another scope,
+//! either individually (optionally under an alias) or all at once with a glob import.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use scope::{Scope, ResolveError};
+use scope::member::{find_member, MemberStore};
+use scope::symbol::SymbolTable;
+use Identifier;
+
+/// Import declaration, bringing names from a namespace scope into a scope.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Import<T> {
+    /// Import of a single name from a namespace, accessible under an alias (e.g.
+    /// `use std::io::Write as W;`). A non-aliased import uses the original identifier as the
+    /// alias.
+    Name {
+        /// Namespace scope containing the imported name.
+        namespace: Rc<RefCell<Scope<T>>>,
+        /// Identifier of the imported name within the namespace.
+        ident: Identifier,
+        /// Identifier under which the imported name is accessible.
+        alias: Identifier,
+    },
+    /// Import of all names in a namespace (e.g. `use std::io::*;`).
+    Glob(Rc<RefCell<Scope<T>>>),
+}
+impl<T> Import<T> {
+    /// Creates an import of a single name from a namespace.
+    pub fn name(namespace: Rc<RefCell<Scope<T>>>, ident: Identifier) -> Import<T> {
+        Import::Name { namespace, alias: ident, ident }
+    }
+    /// Creates an import of a single name from a namespace under an alias.
+    pub fn alias(namespace: Rc<RefCell<Scope<T>>>, ident: Identifier, alias: Identifier)
+        -> Import<T>
+    {
+        Import::Name { namespace, ident, alias }
+    }
+    /// Creates an import of all names in a namespace.
+    pub fn glob(namespace: Rc<RefCell<Scope<T>>>) -> Import<T> {
+        Import::Glob(namespace)
+    }
+}
+
+impl<T> Scope<T> {
+    /// Adds an import declaration to this scope.
+    ///
+    /// # Failures
+    /// Fails with `ResolveError::Ambiguous` if a name import with the same alias has already been
+    /// added to this scope.
+    pub fn import(&mut self, import: Import<T>) -> Result<(), ResolveError> {
+        if let Import::Name { ref alias, .. } = import {
+            let duplicate = self.imports.iter().any(|existing| match *existing {
+                Import::Name { alias: ref existing, .. } => existing == alias,
+                Import::Glob(_) => false,
+            });
+            if duplicate {
+                return Err(ResolveError::Ambiguous(*alias));
+            }
+        }
+        self.imports.push(import);
+        Ok(())
+    }
+
+    /// Resolves an identifier through this scope's imports (but not those of its parents).
+    /// Name imports take precedence over glob imports. Returns `Ok(None)` if no import provides
+    /// the identifier.
+    ///
+    /// # Failures
+    /// Fails with `ResolveError::Ambiguous` if no name import provides the identifier, and
+    /// glob imports of more than one namespace do.
+    pub fn resolve_import<Sym>(&self, ident: &Identifier) -> Result<Option<Sym>, ResolveError>
+        where Sym: Clone, T: SymbolTable<Sym>
+    {
+        self.lookup_import(ident, |_, _| true)
+    }
+
+    /// Lists the symbols provided by this scope's imports (but not those of its parents). Names
+    /// provided by glob imports of more than one namespace are omitted as ambiguous, unless a name
+    /// import provides them.
+    pub fn imported_symbols<Sym>(&self) -> Vec<(Identifier, Sym)>
+        where Sym: Clone, T: SymbolTable<Sym>
+    {
+        let mut idents = vec![];
+        for import in &self.imports {
+            match *import {
+                Import::Name { ref alias, .. } => idents.push(*alias),
+                Import::Glob(ref namespace) => {
+                    let members = namespace.borrow().member_symbols();
+                    idents.extend(members.into_iter().map(|(ident, _)| ident));
+                }
+            }
+        }
+        let mut symbols: Vec<(Identifier, Sym)> = vec![];
+        for ident in idents {
+            if symbols.iter().any(|(existing, _)| existing == &ident) {
+                continue;
+            }
+            if let Ok(Some(symbol)) = self.resolve_import(&ident) {
+                symbols.push((ident, symbol));
+            }
+        }
+        symbols
+    }
+
+    /// Resolves an identifier through this scope's imports, considering only symbols for which
+    /// `accessible` (called with the symbol and the scope defining it) returns `true`. A name
+    /// import providing an inaccessible symbol fails with `ResolveError::Inaccessible`; glob
+    /// imports skip inaccessible symbols.
+    pub(crate) fn lookup_import<Sym, F>(&self, ident: &Identifier, accessible: F)
+        -> Result<Option<Sym>, ResolveError>
+        where Sym: Clone, T: SymbolTable<Sym>, F: Fn(&Sym, &Rc<RefCell<Scope<T>>>) -> bool
+    {
+        for import in &self.imports {
+            if let Import::Name { ref namespace, ident: ref imported, ref alias } = *import {
+                if alias == ident {
+                    return match find_member(namespace, imported) {
+                        Some((symbol, ref owner)) if accessible(&symbol, owner) => Ok(Some(symbol)),
+                        Some(_) => Err(ResolveError::Inaccessible(*ident)),
+                        None => Ok(None),
+                    };
+                }
+            }
+        }
+        let mut found: Option<(&Rc<RefCell<Scope<T>>>, Sym)> = None;
+        for import in &self.imports {
+            if let Import::Glob(ref namespace) = *import {
+                let symbol = match find_member(namespace, ident) {
+                    Some((symbol, ref owner)) if accessible(&symbol, owner) => symbol,
+                    _ => continue,
+                };
+                match found {
+                    Some((prev, _)) if !Rc::ptr_eq(prev, namespace) => {
+                        return Err(ResolveError::Ambiguous(*ident));
+                    },
+                    Some(_) => {},
+                    None => { found = Some((namespace, symbol)); }
+                }
+            }
+        }
+        Ok(found.map(|(_, symbol)| symbol))
+    }
+}
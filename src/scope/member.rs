@@ -0,0 +1,79 @@
+//! Member scope resolution.
+//!
+//! Struct or class types can be given a member scope: a `Scope` holding the type's fields and
+//! methods, attached to the type's symbol through the `Scoped` trait. Member scopes can have base
+//! scopes (see `Scope::bases`) to model inheritance.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use scope::{Scope, Scoped, SymbolStore};
+use scope::symbol::SymbolTable;
+use Identifier;
+
+/// Methods for resolving members of a scope and paths through member scopes.
+pub trait MemberStore<Sym> {
+    /// Resolves a member symbol defined in this scope or one of its base scopes (searched in
+    /// order, depth-first), without searching the lexical parent chain.
+    fn resolve_member(&self, ident: &Identifier) -> Option<Sym>;
+    /// Lists the member symbols of this scope and its base scopes (in order of precedence),
+    /// with base scope members shadowed by another member of the same identifier omitted.
+    fn member_symbols(&self) -> Vec<(Identifier, Sym)>;
+    /// Resolves a path of identifiers (e.g. `a.b.c`, or an `IdentPath`). The first segment is
+    /// resolved normally from this scope; each subsequent segment is resolved as a member of the
+    /// member (or namespace) scope of the symbol resolved for the previous segment.
+    ///
+    /// Returns `None` if the path is empty, any segment fails to resolve, or any non-final
+    /// segment's symbol has no member scope.
+    fn resolve_path(&self, path: &[Identifier]) -> Option<Sym>
+        where Sym: Scoped<Self>, Self: Default + Sized;
+}
+
+impl<Sym, Tbl> MemberStore<Sym> for Scope<Tbl>
+    where Sym: Clone, Tbl: SymbolTable<Sym>
+{
+    fn resolve_member(&self, ident: &Identifier) -> Option<Sym> {
+        match self.item.symbol_get(ident) {
+            Some(symbol) => Some(symbol.clone()),
+            None => self.bases.iter().filter_map(|base| base.borrow().resolve_member(ident)).next(),
+        }
+    }
+    fn member_symbols(&self) -> Vec<(Identifier, Sym)> {
+        let mut members = self.item.symbols()
+            .map(|(ident, symbol)| (*ident, symbol.clone()))
+            .collect::<Vec<_>>();
+        for base in &self.bases {
+            for (ident, symbol) in base.borrow().member_symbols() {
+                if !members.iter().any(|(existing, _)| existing == &ident) {
+                    members.push((ident, symbol));
+                }
+            }
+        }
+        members
+    }
+    fn resolve_path(&self, path: &[Identifier]) -> Option<Sym>
+        where Sym: Scoped<Scope<Tbl>>, Scope<Tbl>: Default
+    {
+        let (first, rest) = path.split_first()?;
+        let mut symbol = self.resolve(first)?;
+        for ident in rest {
+            let members = symbol.scope()?;
+            let member = members.borrow().resolve_member(ident)?;
+            symbol = member;
+        }
+        Some(symbol)
+    }
+}
+
+/// Resolves a member symbol defined in `scope` or one of its base scopes, returning the symbol
+/// along with the scope (either `scope` or one of its bases) in which the member is defined.
+pub(crate) fn find_member<Sym, Tbl>(scope: &Rc<RefCell<Scope<Tbl>>>, ident: &Identifier)
+    -> Option<(Sym, Rc<RefCell<Scope<Tbl>>>)>
+    where Sym: Clone, Tbl: SymbolTable<Sym>
+{
+    let borrowed = scope.borrow();
+    match borrowed.item.symbol_get(ident) {
+        Some(symbol) => Some((symbol.clone(), Rc::clone(scope))),
+        None => borrowed.bases.iter().filter_map(|base| find_member(base, ident)).next(),
+    }
+}
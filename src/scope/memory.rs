@@ -1,18 +1,57 @@
 //! Memory space implementation and scope specification.
 
+use std::cell::{Ref, RefCell};
 use std::fmt::{self, Display};
 use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::rc::Rc;
 
-use scope::Scope;
-use scope::symbol::SymbolTable;
+use scope::{Scope, SymbolStore};
+use scope::symbol::{SymbolTable, SlotTable};
 
-use Identifier;
+use {Identifier, FindType, Type, Typed};
+
+/// Mutability of a memory slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Mutability {
+    /// The slot can be reassigned freely (e.g. `let mut`).
+    #[default]
+    Mutable,
+    /// The slot can be assigned once, either at declaration or later, but cannot be reassigned
+    /// after initialisation (e.g. `let`).
+    Immutable,
+    /// The slot must be initialised at declaration, and can never be assigned (e.g. `const`).
+    Const,
+}
+impl Display for Mutability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        match *self {
+            Mutability::Mutable => write!(f, "mutable"),
+            Mutability::Immutable => write!(f, "immutable"),
+            Mutability::Const => write!(f, "const"),
+        }
+    }
+}
 
 /// Symbol and associated memory (if exists) structure.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SymbolMemory<Sym, Val> {
-    symbol: Sym,
-    memory: Option<Val>,
+    pub(crate) symbol: Sym,
+    pub(crate) memory: Option<Val>,
+    pub(crate) mutability: Mutability,
+}
+impl<Sym, Val> SymbolMemory<Sym, Val> {
+    pub(crate) fn new(symbol: Sym) -> SymbolMemory<Sym, Val> {
+        SymbolMemory { symbol, memory: None, mutability: Mutability::default() }
+    }
+    /// Symbol associated with this memory slot.
+    pub fn symbol(&self) -> &Sym { &self.symbol }
+    /// Value stored in this memory slot, if assigned.
+    pub fn memory(&self) -> Option<&Val> { self.memory.as_ref() }
+    /// Mutability of this memory slot.
+    pub fn mutability(&self) -> Mutability { self.mutability }
 }
 impl<Sym: Display, Val: Display> Display for SymbolMemory<Sym, Val> {
     fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
@@ -25,19 +64,34 @@ impl<Sym: Display, Val: Display> Display for SymbolMemory<Sym, Val> {
 
 /// Scope specialization with both symbol (of type `Sym`) and memory (values of type `Val`)
 /// information
-pub type MemoryScope<Sym, Val> = Scope<HashMap<Identifier, SymbolMemory<Sym, Val>>>;
+pub type MemoryScope<Sym, Val> = Scope<SlotTable<SymbolMemory<Sym, Val>>>;
 
 impl<Sym, Val> SymbolTable<Sym> for HashMap<Identifier, SymbolMemory<Sym, Val>> {
     fn symbol_set(&mut self, ident: Identifier, symbol: Sym) -> Option<Sym> {
-        self.insert(ident, SymbolMemory { symbol: symbol, memory: None }).map(|sm| sm.symbol)
+        self.insert(ident, SymbolMemory::new(symbol)).map(|sm| sm.symbol)
     }
     fn symbol_get(&self, ident: &Identifier) -> Option<&Sym> {
-        self.get(ident).map(|&ref sm| &sm.symbol)
+        self.get(ident).map(|sm| &sm.symbol)
+    }
+    fn symbols(&self) -> Box<dyn Iterator<Item = (&Identifier, &Sym)> + '_> {
+        Box::new(self.iter().map(|(ident, sm)| (ident, &sm.symbol)))
+    }
+}
+impl<Sym, Val> SymbolTable<Sym> for SlotTable<SymbolMemory<Sym, Val>> {
+    fn symbol_set(&mut self, ident: Identifier, symbol: Sym) -> Option<Sym> {
+        self.insert(ident, SymbolMemory::new(symbol)).map(|sm| sm.symbol)
+    }
+    fn symbol_get(&self, ident: &Identifier) -> Option<&Sym> {
+        self.get(ident).map(|sm| &sm.symbol)
+    }
+    fn symbol_slot(&self, ident: &Identifier) -> Option<usize> { self.slot(ident) }
+    fn symbols(&self) -> Box<dyn Iterator<Item = (&Identifier, &Sym)> + '_> {
+        Box::new(self.iter().map(|(ident, sm)| (ident, &sm.symbol)))
     }
 }
 
 /// Methods for accessing a value in a particular memory table.
-////
+///
 /// These methods returns nested `Option`s: the outer `Option` is `Some(...)` if the identifier
 /// exists at all in the table, and the inner `Option` is `Some(...)` if the identifier has
 /// associated memory. This allows for the situation in which an identifier exists, but is
@@ -47,58 +101,442 @@ pub trait MemoryTable<Val> {
     fn memory(&self, ident: &Identifier) -> Option<&Option<Val>>;
     /// Accesses a mutable reference to the value in memory for a particular identifier.
     fn memory_mut(&mut self, ident: &Identifier) -> Option<&mut Option<Val>>;
+    /// Retrieves the mutability of the memory slot for a particular identifier, if the identifier
+    /// exists in the table. Defaults to `Mutability::Mutable` for every existing identifier.
+    fn mutability(&self, ident: &Identifier) -> Option<Mutability> {
+        self.memory(ident).map(|_| Mutability::default())
+    }
+    /// Sets the mutability of the memory slot for a particular identifier. Returns `false` if the
+    /// identifier does not exist in the table, or the table does not track mutability.
+    fn set_mutability(&mut self, _ident: &Identifier, _mutability: Mutability) -> bool { false }
 }
 impl<Sym, Val> MemoryTable<Val> for HashMap<Identifier, SymbolMemory<Sym, Val>> {
     fn memory(&self, ident: &Identifier) -> Option<&Option<Val>> {
-        self.get(ident).map(|&ref sm| &sm.memory)
+        self.get(ident).map(|sm| &sm.memory)
     }
     fn memory_mut(&mut self, ident: &Identifier) -> Option<&mut Option<Val>> {
-        self.get_mut(ident).map(|&mut ref mut sm| &mut sm.memory)
+        self.get_mut(ident).map(|sm| &mut sm.memory)
+    }
+    fn mutability(&self, ident: &Identifier) -> Option<Mutability> {
+        self.get(ident).map(|sm| sm.mutability)
+    }
+    fn set_mutability(&mut self, ident: &Identifier, mutability: Mutability) -> bool {
+        match self.get_mut(ident) {
+            Some(sm) => {
+                sm.mutability = mutability;
+                true
+            },
+            None => false
+        }
+    }
+}
+impl<Sym, Val> MemoryTable<Val> for SlotTable<SymbolMemory<Sym, Val>> {
+    fn memory(&self, ident: &Identifier) -> Option<&Option<Val>> {
+        self.get(ident).map(|sm| &sm.memory)
+    }
+    fn memory_mut(&mut self, ident: &Identifier) -> Option<&mut Option<Val>> {
+        self.get_mut(ident).map(|sm| &mut sm.memory)
+    }
+    fn mutability(&self, ident: &Identifier) -> Option<Mutability> {
+        self.get(ident).map(|sm| sm.mutability)
+    }
+    fn set_mutability(&mut self, ident: &Identifier, mutability: Mutability) -> bool {
+        match self.get_mut(ident) {
+            Some(sm) => {
+                sm.mutability = mutability;
+                true
+            },
+            None => false
+        }
+    }
+}
+
+/// Error resulting from a failed memory operation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemoryError {
+    /// The identifier has not been declared.
+    Undeclared(Identifier),
+    /// The identifier has been declared, but has not been assigned a value.
+    Uninitialised(Identifier),
+    /// The identifier refers to an immutable binding which has already been initialised.
+    Immutable(Identifier),
+    /// The identifier refers to a constant, which cannot be assigned after declaration.
+    Const(Identifier),
+    /// A constant was declared without an initial value.
+    UninitialisedConst(Identifier),
+    /// The value's type does not match the declared type of the identifier.
+    TypeMismatch {
+        /// Identifier being assigned.
+        ident: Identifier,
+        /// Name of the declared type.
+        expected: String,
+        /// Name of the type of the assigned value.
+        found: String,
+    },
+}
+impl Display for MemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        match *self {
+            MemoryError::Undeclared(ref ident) => write!(f, "undeclared identifier: {}", ident),
+            MemoryError::Uninitialised(ref ident) => {
+                write!(f, "use of uninitialised identifier: {}", ident)
+            },
+            MemoryError::Immutable(ref ident) => {
+                write!(f, "cannot assign twice to immutable identifier: {}", ident)
+            },
+            MemoryError::Const(ref ident) => write!(f, "cannot assign to constant: {}", ident),
+            MemoryError::UninitialisedConst(ref ident) => {
+                write!(f, "constant declared without a value: {}", ident)
+            },
+            MemoryError::TypeMismatch { ref ident, ref expected, ref found } => {
+                write!(f, "type mismatch assigning to {}: expected {}, found {}", ident, expected,
+                    found)
+            }
+        }
     }
 }
 
 /// Methods for setting and getting values in a memory store. This is used instead of a
 /// `MemoryTable` when a structure (like a scope) may search through multiple memory tables to
 /// retrieve the value.
+///
+/// As with symbol resolution, scopes strip the marks of an identifier which is not declared in its
+/// own syntax context (see `SymbolStore`).
 pub trait MemoryStore<Val> {
     /// Sets (or updates) the value in memory for an identifier. Returns the previous value (if
-    /// exists). Typically, the identifier should already exist in the store, but may not have
-    /// an existing value.
+    /// exists). The identifier must already be declared in the store, but may not have an
+    /// existing value.
     ///
     /// # Failures
-    /// Can fail if setting value for an identifier that doesn't otherwise exist in the store
-    /// (unless this is allowed in a particular implementation).
-    fn set(&mut self, ident: Identifier, value: Val) -> Result<Option<Val>, String>;
+    /// Fails with `MemoryError::Undeclared` if setting value for an identifier that doesn't
+    /// otherwise exist in the store (unless this is allowed in a particular implementation).
+    /// Fails with `MemoryError::Immutable` if the identifier is an immutable binding which has
+    /// already been initialised, or `MemoryError::Const` if the identifier is a constant.
+    fn set(&mut self, ident: Identifier, value: Val) -> Result<Option<Val>, MemoryError>;
     /// Retrieves the value for a particular identifier, if exists.
     fn get(&self, ident: &Identifier) -> Option<Val>;
+    /// Reads the value for a particular identifier, distinguishing between undeclared and
+    /// unassigned identifiers.
+    ///
+    /// # Failures
+    /// Fails with `MemoryError::Undeclared` if the identifier doesn't exist in the store, or
+    /// `MemoryError::Uninitialised` if it exists but has not been assigned a value.
+    fn read(&self, ident: &Identifier) -> Result<Val, MemoryError>;
+    /// Assigns a value to a previously-declared identifier. Equivalent to `set`.
+    ///
+    /// # Failures
+    /// See `set`.
+    fn assign(&mut self, ident: Identifier, value: Val) -> Result<Option<Val>, MemoryError> {
+        self.set(ident, value)
+    }
+    /// Calls `f` with a reference to the value for a particular identifier, without cloning the
+    /// value, and returns the result of `f`.
+    ///
+    /// # Failures
+    /// Fails (without calling `f`) as `read`.
+    fn with_value<R, F: FnOnce(&Val) -> R>(&self, ident: &Identifier, f: F)
+        -> Result<R, MemoryError>;
+    /// Calls `f` with a mutable reference to the value for a particular identifier, allowing the
+    /// value to be modified in place, and returns the result of `f`.
+    ///
+    /// # Failures
+    /// Fails (without calling `f`) as `read`, or with `MemoryError::Immutable` or
+    /// `MemoryError::Const` if the identifier is not mutable.
+    fn update<R, F: FnOnce(&mut Val) -> R>(&mut self, ident: &Identifier, f: F)
+        -> Result<R, MemoryError>;
 }
-impl<Val: Clone, T> MemoryStore<Val> for Scope<T> where T: MemoryTable<Val> {
-    fn set(&mut self, ident: Identifier, value: Val) -> Result<Option<Val>, String> {
+impl<T> Scope<T> {
+    /// Identifier under which `ident` is declared in this scope or its parents, stripping marks
+    /// outward (see `SyntaxContext`), or `ident` itself if it is not declared at all.
+    fn binding<Val>(&self, ident: &Identifier) -> Identifier where T: MemoryTable<Val> {
+        if ident.context().is_root() {
+            return *ident;
+        }
+        ident.outward().find(|candidate| self.declares(candidate)).unwrap_or(*ident)
+    }
+    fn declares<Val>(&self, ident: &Identifier) -> bool where T: MemoryTable<Val> {
+        self.item.memory(ident).is_some()
+            || self.parent.as_ref().map_or(false, |parent| parent.borrow().declares(ident))
+    }
+    fn set_exact<Val: Clone>(&mut self, ident: Identifier, value: Val)
+        -> Result<Option<Val>, MemoryError>
+        where T: MemoryTable<Val>
+    {
+        let mutability = self.item.mutability(&ident);
         match self.item.memory_mut(&ident) {
-            Some(&mut ref mut curr_mem) => {
+            Some(curr_mem) => {
+                check_assign(&ident, mutability.unwrap_or_default(), curr_mem)?;
                 let ret = curr_mem.clone();
                 *curr_mem = Some(value);
                 Ok(ret)
             },
             None => {
                 match self.parent {
-                    Some(ref mut parent) => parent.borrow_mut().set(ident, value),
-                    None => Err(format!("attempt to set memory for missing symbol: {}", ident))
+                    Some(ref mut parent) => parent.borrow_mut().set_exact(ident, value),
+                    None => Err(MemoryError::Undeclared(ident))
                 }
             }
         }
     }
-    fn get(&self, ident: &Identifier) -> Option<Val> {
+    fn get_exact<Val: Clone>(&self, ident: &Identifier) -> Option<Val> where T: MemoryTable<Val> {
         match self.item.memory(ident) {
-            Some(&ref curr_mem) => {
+            Some(curr_mem) => {
                 curr_mem.clone()
             },
             None => {
                 match self.parent {
-                    Some(ref parent) => parent.borrow().get(ident),
+                    Some(ref parent) => parent.borrow().get_exact(ident),
                     None => None
                 }
             }
         }
     }
+    fn with_value_exact<Val, R, F: FnOnce(&Val) -> R>(&self, ident: &Identifier, f: F)
+        -> Result<R, MemoryError>
+        where T: MemoryTable<Val>
+    {
+        match self.item.memory(ident) {
+            Some(Some(value)) => Ok(f(value)),
+            Some(None) => Err(MemoryError::Uninitialised(*ident)),
+            None => {
+                match self.parent {
+                    Some(ref parent) => parent.borrow().with_value_exact(ident, f),
+                    None => Err(MemoryError::Undeclared(*ident))
+                }
+            }
+        }
+    }
+    fn update_exact<Val, R, F: FnOnce(&mut Val) -> R>(&mut self, ident: &Identifier, f: F)
+        -> Result<R, MemoryError>
+        where T: MemoryTable<Val>
+    {
+        let mutability = self.item.mutability(ident);
+        match self.item.memory_mut(ident) {
+            Some(curr_mem) => {
+                check_update(ident, mutability.unwrap_or_default(), curr_mem)?;
+                Ok(f(curr_mem.as_mut().unwrap()))
+            },
+            None => {
+                match self.parent {
+                    Some(ref parent) => parent.borrow_mut().update_exact(ident, f),
+                    None => Err(MemoryError::Undeclared(*ident))
+                }
+            }
+        }
+    }
+}
+
+impl<Val: Clone, T> MemoryStore<Val> for Scope<T> where T: MemoryTable<Val> {
+    fn set(&mut self, ident: Identifier, value: Val) -> Result<Option<Val>, MemoryError> {
+        let ident = self.binding(&ident);
+        self.set_exact(ident, value)
+    }
+    fn get(&self, ident: &Identifier) -> Option<Val> {
+        self.get_exact(&self.binding(ident))
+    }
+    fn read(&self, ident: &Identifier) -> Result<Val, MemoryError> {
+        self.with_value(ident, Val::clone)
+    }
+    fn with_value<R, F: FnOnce(&Val) -> R>(&self, ident: &Identifier, f: F)
+        -> Result<R, MemoryError>
+    {
+        self.with_value_exact(&self.binding(ident), f)
+    }
+    fn update<R, F: FnOnce(&mut Val) -> R>(&mut self, ident: &Identifier, f: F)
+        -> Result<R, MemoryError>
+    {
+        let ident = self.binding(ident);
+        self.update_exact(&ident, f)
+    }
+}
+
+/// Guard for a value held by a shared scope, returned by `SharedMemoryStore::get_ref`.
+///
+/// The guard owns (an `Rc` of) the scope holding the value, so it remains valid even if the scope
+/// chain it was found through changes. `borrow` borrows the value without cloning it.
+pub struct ValueRef<T, Val> {
+    scope: Rc<RefCell<Scope<T>>>,
+    ident: Identifier,
+    value: PhantomData<Val>,
+}
+impl<T, Val> ValueRef<T, Val> where T: MemoryTable<Val> {
+    /// Scope holding the value.
+    pub fn scope(&self) -> &Rc<RefCell<Scope<T>>> { &self.scope }
+    /// Borrows the value. The scope holding the value cannot be mutably borrowed while the
+    /// returned `Ref` is alive.
+    ///
+    /// # Failures
+    /// Fails with `MemoryError::Undeclared` or `MemoryError::Uninitialised` if the identifier has
+    /// since been redeclared in the scope holding the value without a value.
+    ///
+    /// # Panics
+    /// Panics if the scope holding the value is currently mutably borrowed.
+    pub fn borrow(&self) -> Result<Ref<'_, Val>, MemoryError> {
+        let ident = &self.ident;
+        Ref::filter_map(self.scope.borrow(), |scope| scope.item.memory(ident)?.as_ref())
+            .map_err(|scope| match scope.item.memory(ident) {
+                Some(_) => MemoryError::Uninitialised(*ident),
+                None => MemoryError::Undeclared(*ident),
+            })
+    }
+}
+impl<T, Val> fmt::Debug for ValueRef<T, Val> where T: MemoryTable<Val>, Val: fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        match self.borrow() {
+            Ok(value) => f.debug_tuple("ValueRef").field(&*value).finish(),
+            Err(error) => f.debug_tuple("ValueRef").field(&error).finish(),
+        }
+    }
+}
+
+/// Borrowed access to values in shared scopes, without cloning the value.
+///
+/// This method is implemented on `Rc<RefCell<Scope<T>>>`, since the returned guard holds the
+/// scope containing the value (which may be any scope along the parent chain).
+pub trait SharedMemoryStore<T, Val> {
+    /// Finds the value for a particular identifier, returning a guard through which it can be
+    /// borrowed.
+    ///
+    /// # Failures
+    /// Fails with `MemoryError::Undeclared` if the identifier doesn't exist in the scope, or
+    /// `MemoryError::Uninitialised` if it exists but has not been assigned a value.
+    fn get_ref(&self, ident: &Identifier) -> Result<ValueRef<T, Val>, MemoryError>;
+}
+impl<T, Val> SharedMemoryStore<T, Val> for Rc<RefCell<Scope<T>>> where T: MemoryTable<Val> {
+    fn get_ref(&self, ident: &Identifier) -> Result<ValueRef<T, Val>, MemoryError> {
+        let ident = self.borrow().binding(ident);
+        let mut current = Rc::clone(self);
+        loop {
+            let parent = {
+                let scope = current.borrow();
+                match scope.item.memory(&ident) {
+                    Some(Some(_)) => None,
+                    Some(None) => return Err(MemoryError::Uninitialised(ident)),
+                    None => match scope.parent {
+                        Some(ref parent) => Some(Rc::clone(parent)),
+                        None => return Err(MemoryError::Undeclared(ident)),
+                    },
+                }
+            };
+            match parent {
+                Some(parent) => { current = parent; },
+                None => return Ok(ValueRef { scope: current, ident, value: PhantomData }),
+            }
+        }
+    }
+}
+
+/// Checks whether a memory slot with the specified mutability and current value can be updated in
+/// place.
+pub(crate) fn check_update<Val>(ident: &Identifier, mutability: Mutability, current: &Option<Val>)
+    -> Result<(), MemoryError>
+{
+    match *current {
+        None => Err(MemoryError::Uninitialised(*ident)),
+        Some(_) => check_assign(ident, mutability, current),
+    }
+}
+
+/// Checks whether a memory slot with the specified mutability and current value can be assigned.
+pub(crate) fn check_assign<Val>(ident: &Identifier, mutability: Mutability, current: &Option<Val>)
+    -> Result<(), MemoryError>
+{
+    match mutability {
+        Mutability::Mutable => Ok(()),
+        Mutability::Immutable if current.is_none() => Ok(()),
+        Mutability::Immutable => Err(MemoryError::Immutable(*ident)),
+        Mutability::Const => Err(MemoryError::Const(*ident)),
+    }
+}
+
+/// Checks whether an identifier can be declared with the specified mutability and initial value.
+pub(crate) fn check_declare<Val>(ident: &Identifier, mutability: Mutability, value: &Option<Val>)
+    -> Result<(), MemoryError>
+{
+    match (mutability, value) {
+        (Mutability::Const, &None) => Err(MemoryError::UninitialisedConst(*ident)),
+        _ => Ok(()),
+    }
+}
+
+/// Methods for declaring identifiers (with symbols of type `Sym`) in a memory store holding values
+/// of type `Val`.
+pub trait DeclarationStore<Sym, Val> {
+    /// Declares an identifier in this store with the specified mutability and (optional) initial
+    /// value. Returns the previous symbol if the identifier had already been declared in this
+    /// store (in which case any previous value is discarded).
+    ///
+    /// # Failures
+    /// Fails with `MemoryError::UninitialisedConst` (leaving the store unchanged) if `mutability`
+    /// is `Mutability::Const` and no value is provided, since the constant could never be
+    /// initialised.
+    fn declare_with(&mut self, ident: Identifier, symbol: Sym, mutability: Mutability,
+        value: Option<Val>) -> Result<Option<Sym>, MemoryError>;
+    /// Declares a mutable identifier in this store, without assigning a value. Returns the
+    /// previous symbol if the identifier had already been declared in this store.
+    fn declare(&mut self, ident: Identifier, symbol: Sym) -> Option<Sym>;
+    /// Declares a mutable identifier in this store and assigns its initial value. Returns the
+    /// previous symbol if the identifier had already been declared in this store.
+    fn declare_init(&mut self, ident: Identifier, symbol: Sym, value: Val) -> Option<Sym>;
+}
+impl<Sym, Val, T> DeclarationStore<Sym, Val> for Scope<T>
+    where T: SymbolTable<Sym> + MemoryTable<Val>
+{
+    fn declare_with(&mut self, ident: Identifier, symbol: Sym, mutability: Mutability,
+        value: Option<Val>) -> Result<Option<Sym>, MemoryError>
+    {
+        check_declare(&ident, mutability, &value)?;
+        Ok(self.declare_unchecked(ident, symbol, mutability, value))
+    }
+    fn declare(&mut self, ident: Identifier, symbol: Sym) -> Option<Sym> {
+        self.declare_unchecked(ident, symbol, Mutability::Mutable, None)
+    }
+    fn declare_init(&mut self, ident: Identifier, symbol: Sym, value: Val) -> Option<Sym> {
+        self.declare_unchecked(ident, symbol, Mutability::Mutable, Some(value))
+    }
+}
+impl<T> Scope<T> {
+    /// Declares an identifier without checking the declaration (see `check_declare`).
+    fn declare_unchecked<Sym, Val>(&mut self, ident: Identifier, symbol: Sym,
+        mutability: Mutability, value: Option<Val>) -> Option<Sym>
+        where T: SymbolTable<Sym> + MemoryTable<Val>
+    {
+        let prev = self.item.symbol_set(ident, symbol);
+        self.item.set_mutability(&ident, mutability);
+        if let Some(memory) = self.item.memory_mut(&ident) {
+            *memory = value;
+        }
+        prev
+    }
+}
+
+/// Methods for assigning values to identifiers, checking that each value's type matches the
+/// declared type of the identifier's symbol (of type `Sym`).
+pub trait TypedMemoryStore<Sym, Val> {
+    /// Assigns a value to a previously-declared identifier (as `MemoryStore::assign`), first
+    /// checking that the value's type matches the symbol's type (if the symbol has a type).
+    ///
+    /// # Failures
+    /// Fails with `MemoryError::TypeMismatch` if the types do not match, or as
+    /// `MemoryStore::assign`.
+    fn assign_typed(&mut self, ident: Identifier, value: Val) -> Result<Option<Val>, MemoryError>;
+}
+impl<Sym, Val> TypedMemoryStore<Sym, Val> for MemoryScope<Sym, Val>
+    where Sym: Clone + Typed<Val::Output>, Val: FindType
+{
+    fn assign_typed(&mut self, ident: Identifier, value: Val) -> Result<Option<Val>, MemoryError> {
+        let symbol: Sym = self.resolve(&ident)
+            .ok_or(MemoryError::Undeclared(ident))?;
+        if let Some(expected) = symbol.ty() {
+            let found = value.ty();
+            if expected != found {
+                return Err(MemoryError::TypeMismatch {
+                    ident,
+                    expected: expected.name().to_string(),
+                    found: found.name().to_string(),
+                });
+            }
+        }
+        self.assign(ident, value)
+    }
 }
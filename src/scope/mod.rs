@@ -7,8 +7,32 @@ pub mod symbol;
 pub use self::symbol::*;
 pub mod memory;
 pub use self::memory::*;
+pub mod member;
+pub use self::member::*;
+pub mod import;
+pub use self::import::*;
+pub mod visibility;
+pub use self::visibility::*;
+pub mod policy;
+pub use self::policy::*;
+pub mod capture;
+pub use self::capture::*;
+pub mod tree;
+pub use self::tree::*;
+pub mod persistent;
+pub use self::persistent::*;
+pub mod call;
+pub use self::call::*;
+pub mod heap;
+pub use self::heap::*;
+pub mod snapshot;
+pub use self::snapshot::*;
+#[cfg(feature = "serde")] pub mod serialize;
+#[cfg(feature = "serde")] pub use self::serialize::Shared;
 
 use std::cell::RefCell;
+use std::mem;
+use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
 /// General scope object with underlying data structure `T`.
@@ -26,33 +50,68 @@ pub struct Scope<T> {
     /// This is maintained as an `Rc<RefCell<...>>` since a single scope can be the parent of
     /// multiple other scopes.
     pub parent: Option<Rc<RefCell<Scope<T>>>>,
+    /// Base scopes of this scope (e.g. the member scopes of base classes, for a class member
+    /// scope), in order of precedence.
+    ///
+    /// Lookups that fail in this scope search the base scopes (and their bases) before falling
+    /// back to the lexical parent. Base scopes must not form a cycle.
+    pub bases: Vec<Rc<RefCell<Scope<T>>>>,
+    /// Import declarations in this scope.
+    ///
+    /// Lookups that fail in this scope and its base scopes search the imports before falling
+    /// back to the lexical parent.
+    pub imports: Vec<Import<T>>,
+    /// Policy for handling conflicting definitions in this scope (see `DefineStore`). New scopes
+    /// pushed from this scope inherit this policy, unless this scope is mutably borrowed at the
+    /// time of the push (in which case the new scope uses the default policy).
+    pub define_policy: DefinePolicy,
+    /// Kind of this scope. Function scopes act as boundaries for capture analysis.
+    pub kind: ScopeKind,
+    /// Outer symbols captured by this scope, if it is a function scope (see `CaptureStore`).
+    pub captures: Vec<Capture>,
 }
 impl<T: Default> Scope<T> {
     fn new_with_parent(parent: Option<Rc<RefCell<Scope<T>>>>) -> Scope<T> {
+        // the parent may already be mutably borrowed (e.g. by a tree walker which pushes a scope
+        // while modifying the current one); fall back to the default policy in that case
+        let define_policy = parent.as_ref()
+            .and_then(|parent| parent.try_borrow().ok().map(|parent| parent.define_policy))
+            .unwrap_or_default();
         Scope {
             item: T::default(),
-            parent: parent,
+            parent,
+            bases: vec![],
+            imports: vec![],
+            define_policy,
+            kind: ScopeKind::default(),
+            captures: vec![],
         }
     }
 }
+impl<T> Scope<T> {
+    /// Adds a base scope to this scope, with lower precedence than any existing base scopes.
+    pub fn add_base(&mut self, base: Rc<RefCell<Scope<T>>>) {
+        self.bases.push(base);
+    }
+}
 impl<T: Default> Default for Scope<T> {
     fn default() -> Scope<T> {
-        Scope {
-            item: T::default(),
-            parent: None,
-        }
+        Scope::new_with_parent(None)
     }
 }
 
 /// General stack trait for an object which contains its own stack management.
 ///
-/// Handles typical `push` and `pop` operations.
+/// Handles typical `push`, `pop` and `peek` operations. The implementing object acts as a handle
+/// to the top of the stack.
 pub trait Stack: Sized + PartialEq {
     /// Push the current object down the stack, and create and return the new object (which
     /// maintains a stack pointer to the previous object on the stack).
     fn push(&self) -> Self;
-    /// Pop the current object off the stack, and return the next object on the stack (if exists).
-    fn pop(&self) -> Option<Self>;
+    /// Pop the current object off the stack: replaces this object with the next object on the
+    /// stack, and returns the popped object. Returns `None` (leaving this object unchanged) if
+    /// there is no next object on the stack.
+    fn pop(&mut self) -> Option<Self>;
     /// Peek at the next object on the stack (but don't pop the current one off).
     fn peek(&self) -> Option<Self>;
 }
@@ -60,16 +119,48 @@ impl<T: Default + PartialEq> Stack for Rc<RefCell<Scope<T>>> {
     fn push(&self) -> Rc<RefCell<Scope<T>>> {
         Rc::new(RefCell::new(Scope::<T>::new_with_parent(Some(Rc::clone(self)))))
     }
-    fn pop(&self) -> Option<Rc<RefCell<Scope<T>>>> {
-        match self.borrow().parent {
-            Some(ref scope) => Some(Rc::clone(scope)),
-            None => None
-        }
+    fn pop(&mut self) -> Option<Rc<RefCell<Scope<T>>>> {
+        let parent = self.peek()?;
+        Some(mem::replace(self, parent))
     }
     fn peek(&self) -> Option<Rc<RefCell<Scope<T>>>> {
-        match self.borrow().parent {
-            Some(ref scope) => Some(Rc::clone(scope)),
-            None => None,
+        self.borrow().parent.as_ref().map(Rc::clone)
+    }
+}
+
+/// RAII guard which pushes a new object onto a stack when created, and restores the previous top
+/// of the stack when dropped.
+///
+/// The guard dereferences to the stack handle, which points to the newly-pushed object while the
+/// guard is alive. Since the previous top of the stack is restored on drop, early returns (e.g.
+/// through `?`) cannot leave the stack unbalanced.
+pub struct ScopeGuard<'a, S: 'a + Stack> {
+    current: &'a mut S,
+    previous: Option<S>,
+}
+impl<'a, S: Stack> ScopeGuard<'a, S> {
+    /// Pushes a new object onto the stack whose top is `current`, updating `current` to point to
+    /// the new object until the guard is dropped.
+    pub fn push(current: &'a mut S) -> ScopeGuard<'a, S> {
+        let pushed = current.push();
+        let previous = mem::replace(current, pushed);
+        ScopeGuard {
+            current,
+            previous: Some(previous),
+        }
+    }
+}
+impl<'a, S: Stack> Deref for ScopeGuard<'a, S> {
+    type Target = S;
+    fn deref(&self) -> &S { self.current }
+}
+impl<'a, S: Stack> DerefMut for ScopeGuard<'a, S> {
+    fn deref_mut(&mut self) -> &mut S { self.current }
+}
+impl<'a, S: Stack> Drop for ScopeGuard<'a, S> {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            *self.current = previous;
         }
     }
 }
@@ -82,5 +173,5 @@ pub trait Scoped<Sc: Default> {
     /// Accesses an `Rc` pointer to the scope (if exists).
     fn scope(&self) -> Option<Rc<RefCell<Sc>>>;
     /// Ses the `Rc` pointer to the scope, or `None` if no scope should exist.
-    fn set_scope(&mut self, Option<Rc<RefCell<Sc>>>);
+    fn set_scope(&mut self, scope: Option<Rc<RefCell<Sc>>>);
 }
@@ -0,0 +1,271 @@
+//! Persistent scope implementation.
+//!
+//! `PersistentScope` provides the same symbol and memory operations as `MemoryScope`, but is
+//! built from immutable, reference-counted frames with copy-on-write updates. Cloning a
+//! `PersistentScope` is cheap and produces an independent snapshot: later changes to either copy
+//! do not affect the other. This makes speculative analysis (e.g. trying overloads, or
+//! backtracking type inference) straightforward: take a snapshot, try the analysis, and roll back
+//! to the snapshot if it fails.
+//!
+//! The bindings of each frame are held in persistent (structurally-shared) collections, so a
+//! write after a snapshot copies only the frames between the top of the chain and the frame being
+//! modified, and within that frame only the path to the modified binding: O(depth + log n) rather
+//! than the size of the frames.
+
+use std::rc::Rc;
+
+use im_rc::{HashMap, Vector};
+
+use scope::{Stack, SymbolStore, SymbolLocation, MemoryStore, SymbolMemory, MemoryError,
+    DeclarationStore, Mutability};
+use scope::memory::{check_assign, check_declare, check_update};
+use Identifier;
+
+/// Persistent table of bindings, allocating slots in order of first definition (as `SlotTable`).
+#[derive(Debug, Clone, PartialEq)]
+struct Table<Sym, Val> {
+    bindings: HashMap<Identifier, (usize, SymbolMemory<Sym, Val>)>,
+    slots: Vector<Identifier>,
+}
+impl<Sym, Val> Table<Sym, Val> {
+    fn get(&self, ident: &Identifier) -> Option<&SymbolMemory<Sym, Val>> {
+        self.bindings.get(ident).map(|(_, sm)| sm)
+    }
+    fn slot(&self, ident: &Identifier) -> Option<usize> {
+        self.bindings.get(ident).map(|&(slot, _)| slot)
+    }
+    fn iter(&self) -> impl Iterator<Item = (&Identifier, &SymbolMemory<Sym, Val>)> {
+        self.slots.iter().map(move |ident| (ident, &self.bindings[ident].1))
+    }
+}
+impl<Sym: Clone, Val: Clone> Table<Sym, Val> {
+    fn get_mut(&mut self, ident: &Identifier) -> Option<&mut SymbolMemory<Sym, Val>> {
+        self.bindings.get_mut(ident).map(|(_, sm)| sm)
+    }
+    fn define(&mut self, ident: Identifier, symbol: Sym) -> Option<Sym> {
+        if let Some(sm) = self.get_mut(&ident) {
+            return Some(::std::mem::replace(&mut sm.symbol, symbol));
+        }
+        self.bindings.insert(ident, (self.slots.len(), SymbolMemory::new(symbol)));
+        self.slots.push_back(ident);
+        None
+    }
+    /// Binds an identifier to a symbol and memory, replacing any previous binding (but keeping
+    /// its slot). Returns the previous symbol, if any.
+    fn declare(&mut self, ident: Identifier, symbol: Sym, mutability: Mutability,
+        memory: Option<Val>) -> Option<Sym>
+    {
+        let sm = SymbolMemory { symbol, memory, mutability };
+        if let Some((_, existing)) = self.bindings.get_mut(&ident) {
+            return Some(::std::mem::replace(existing, sm).symbol);
+        }
+        self.bindings.insert(ident, (self.slots.len(), sm));
+        self.slots.push_back(ident);
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Frame<Sym, Val> {
+    table: Table<Sym, Val>,
+    parent: Option<Rc<Frame<Sym, Val>>>,
+}
+impl<Sym, Val> Frame<Sym, Val> {
+    fn new(parent: Option<Rc<Frame<Sym, Val>>>) -> Frame<Sym, Val> {
+        Frame {
+            table: Table { bindings: HashMap::new(), slots: Vector::new() },
+            parent,
+        }
+    }
+}
+
+/// Scope with symbol (of type `Sym`) and memory (values of type `Val`) information, with cheap
+/// snapshots and rollback.
+///
+/// Each `PersistentScope` is a handle to the top frame of a chain of frames; frames are shared
+/// between snapshots until modified.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersistentScope<Sym, Val> {
+    top: Rc<Frame<Sym, Val>>,
+}
+impl<Sym, Val> Default for PersistentScope<Sym, Val> {
+    fn default() -> PersistentScope<Sym, Val> {
+        PersistentScope { top: Rc::new(Frame::new(None)) }
+    }
+}
+impl<Sym: Clone, Val: Clone> PersistentScope<Sym, Val> {
+    /// Creates a new root scope.
+    pub fn new() -> PersistentScope<Sym, Val> { PersistentScope::default() }
+
+    /// Takes a snapshot of this scope (including its parent chain). This is equivalent to
+    /// `clone`, and is a constant-time operation.
+    pub fn snapshot(&self) -> PersistentScope<Sym, Val> { self.clone() }
+
+    /// Rolls this scope back to a previously-taken snapshot, discarding all changes since.
+    pub fn rollback(&mut self, snapshot: PersistentScope<Sym, Val>) {
+        *self = snapshot;
+    }
+
+    /// Number of scopes in the chain from this scope to the root scope (inclusive).
+    pub fn depth(&self) -> usize {
+        let mut depth = 1;
+        let mut frame = &self.top;
+        while let Some(ref parent) = frame.parent {
+            depth += 1;
+            frame = parent;
+        }
+        depth
+    }
+}
+
+impl<Sym: Clone + PartialEq, Val: Clone + PartialEq> Stack for PersistentScope<Sym, Val> {
+    fn push(&self) -> PersistentScope<Sym, Val> {
+        PersistentScope { top: Rc::new(Frame::new(Some(Rc::clone(&self.top)))) }
+    }
+    fn pop(&mut self) -> Option<PersistentScope<Sym, Val>> {
+        let parent = self.peek()?;
+        Some(::std::mem::replace(self, parent))
+    }
+    fn peek(&self) -> Option<PersistentScope<Sym, Val>> {
+        self.top.parent.as_ref().map(|parent| PersistentScope { top: Rc::clone(parent) })
+    }
+}
+
+impl<Sym: Clone, Val: Clone> SymbolStore<Sym> for PersistentScope<Sym, Val> {
+    fn define(&mut self, ident: Identifier, symbol: Sym) -> Option<Sym> {
+        Rc::make_mut(&mut self.top).table.define(ident, symbol)
+    }
+    fn resolve(&self, ident: &Identifier) -> Option<Sym> {
+        let ident = self.binding(ident);
+        self.find(&ident).and_then(|(_, table)| table.get(&ident)).map(|sm| sm.symbol.clone())
+    }
+    fn resolve_with_location(&self, ident: &Identifier) -> Option<(Sym, SymbolLocation)> {
+        let ident = self.binding(ident);
+        let (depth, table) = self.find(&ident)?;
+        let slot = table.slot(&ident)?;
+        table.get(&ident).map(|sm| (sm.symbol.clone(), SymbolLocation { depth, slot }))
+    }
+    fn visible_symbols(&self) -> Vec<(Identifier, Sym)> {
+        let mut visible: Vec<(Identifier, Sym)> = vec![];
+        let mut frame = Some(&self.top);
+        while let Some(current) = frame {
+            for (ident, sm) in current.table.iter() {
+                if !visible.iter().any(|(existing, _)| existing == ident) {
+                    visible.push((*ident, sm.symbol.clone()));
+                }
+            }
+            frame = current.parent.as_ref();
+        }
+        visible
+    }
+}
+
+impl<Sym, Val> PersistentScope<Sym, Val> {
+    /// Finds the table of the innermost frame binding exactly this identifier (without stripping
+    /// marks), along with the number of parent hops to that frame.
+    fn find(&self, ident: &Identifier) -> Option<(usize, &Table<Sym, Val>)> {
+        let mut frame = &self.top;
+        let mut depth = 0;
+        loop {
+            if frame.table.get(ident).is_some() {
+                return Some((depth, &frame.table));
+            }
+            match frame.parent {
+                Some(ref parent) => {
+                    frame = parent;
+                    depth += 1;
+                },
+                None => return None,
+            }
+        }
+    }
+    /// Identifier under which `ident` is bound in this scope, stripping marks outward (see
+    /// `SyntaxContext`), or `ident` itself if it is not bound at all.
+    fn binding(&self, ident: &Identifier) -> Identifier {
+        if ident.context().is_root() {
+            return *ident;
+        }
+        ident.outward().find(|candidate| self.find(candidate).is_some()).unwrap_or(*ident)
+    }
+    fn memory(&self, ident: &Identifier) -> Option<&Option<Val>> {
+        let ident = self.binding(ident);
+        self.find(&ident).and_then(|(_, table)| table.get(&ident)).map(|sm| &sm.memory)
+    }
+    /// Retrieves a reference to the value for a particular identifier, without cloning the value.
+    ///
+    /// # Failures
+    /// Fails with `MemoryError::Undeclared` if the identifier doesn't exist in the scope, or
+    /// `MemoryError::Uninitialised` if it exists but has not been assigned a value.
+    pub fn get_ref(&self, ident: &Identifier) -> Result<&Val, MemoryError> {
+        match self.memory(ident) {
+            Some(memory) => {
+                memory.as_ref().ok_or(MemoryError::Uninitialised(*ident))
+            },
+            None => Err(MemoryError::Undeclared(*ident)),
+        }
+    }
+}
+impl<Sym: Clone, Val: Clone> PersistentScope<Sym, Val> {
+    fn memory_mut(&mut self, ident: &Identifier)
+        -> Result<(Mutability, &mut Option<Val>), MemoryError>
+    {
+        let ident = &self.binding(ident);
+        // find the frame containing the identifier first, so that no frame is copied if the
+        // identifier is undeclared
+        let depth = match self.find(ident) {
+            Some((depth, _)) => depth,
+            None => return Err(MemoryError::Undeclared(*ident)),
+        };
+        // copy-on-write each frame along the path to the frame containing the identifier
+        let mut frame = Rc::make_mut(&mut self.top);
+        for _ in 0..depth {
+            frame = match frame.parent {
+                Some(ref mut parent) => Rc::make_mut(parent),
+                None => break,
+            };
+        }
+        frame.table.get_mut(ident).map(|sm| (sm.mutability, &mut sm.memory))
+            .ok_or(MemoryError::Undeclared(*ident))
+    }
+}
+
+impl<Sym: Clone, Val: Clone> MemoryStore<Val> for PersistentScope<Sym, Val> {
+    fn set(&mut self, ident: Identifier, value: Val) -> Result<Option<Val>, MemoryError> {
+        let (mutability, memory) = self.memory_mut(&ident)?;
+        check_assign(&ident, mutability, memory)?;
+        Ok(memory.replace(value))
+    }
+    fn get(&self, ident: &Identifier) -> Option<Val> {
+        self.memory(ident).and_then(|memory| memory.clone())
+    }
+    fn read(&self, ident: &Identifier) -> Result<Val, MemoryError> {
+        self.with_value(ident, Val::clone)
+    }
+    fn with_value<R, F: FnOnce(&Val) -> R>(&self, ident: &Identifier, f: F)
+        -> Result<R, MemoryError>
+    {
+        self.get_ref(ident).map(f)
+    }
+    fn update<R, F: FnOnce(&mut Val) -> R>(&mut self, ident: &Identifier, f: F)
+        -> Result<R, MemoryError>
+    {
+        let (mutability, memory) = self.memory_mut(ident)?;
+        check_update(ident, mutability, memory)?;
+        Ok(f(memory.as_mut().unwrap()))
+    }
+}
+
+impl<Sym: Clone, Val: Clone> DeclarationStore<Sym, Val> for PersistentScope<Sym, Val> {
+    fn declare_with(&mut self, ident: Identifier, symbol: Sym, mutability: Mutability,
+        value: Option<Val>) -> Result<Option<Sym>, MemoryError>
+    {
+        check_declare(&ident, mutability, &value)?;
+        Ok(Rc::make_mut(&mut self.top).table.declare(ident, symbol, mutability, value))
+    }
+    fn declare(&mut self, ident: Identifier, symbol: Sym) -> Option<Sym> {
+        Rc::make_mut(&mut self.top).table.declare(ident, symbol, Mutability::Mutable, None)
+    }
+    fn declare_init(&mut self, ident: Identifier, symbol: Sym, value: Val) -> Option<Sym> {
+        Rc::make_mut(&mut self.top).table.declare(ident, symbol, Mutability::Mutable, Some(value))
+    }
+}
@@ -0,0 +1,115 @@
+//! Policies for handling redefinition and shadowing of symbols.
+
+use std::fmt;
+
+use scope::{Scope, SymbolStore};
+use scope::symbol::SymbolTable;
+use span::{Span, HasSpan};
+use Identifier;
+
+/// Action to take when a definition conflicts with an existing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ConflictPolicy {
+    /// Allow the definition silently.
+    #[default]
+    Allow,
+    /// Allow the definition, but report the conflict.
+    Warn,
+    /// Reject the definition, and report the conflict.
+    Error,
+}
+
+/// Policies for handling conflicting definitions in a scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DefinePolicy {
+    /// Policy for defining an identifier already defined in the same scope.
+    pub redefinition: ConflictPolicy,
+    /// Policy for defining an identifier which shadows a definition in an outer scope.
+    pub shadowing: ConflictPolicy,
+}
+
+/// Kind of conflict between a new definition and an existing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConflictKind {
+    /// The identifier is already defined in the same scope.
+    Redefinition,
+    /// The identifier is defined in an outer scope.
+    Shadowing,
+}
+
+/// Conflict between a new definition and an existing one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefineConflict<Sym> {
+    /// Kind of conflict.
+    pub kind: ConflictKind,
+    /// Conflicting identifier.
+    pub ident: Identifier,
+    /// The existing definition.
+    pub previous: Sym,
+    /// Location of the existing definition in the source (if known).
+    pub previous_span: Option<Span>,
+}
+impl<Sym> fmt::Display for DefineConflict<Sym> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        match self.kind {
+            ConflictKind::Redefinition => write!(f, "redefinition of {}", self.ident)?,
+            ConflictKind::Shadowing => write!(f, "{} shadows an outer definition", self.ident)?,
+        }
+        match self.previous_span {
+            Some(span) => write!(f, " (previously defined at {})", span),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Methods for defining symbols subject to the scope's `DefinePolicy`.
+pub trait DefineStore<Sym> {
+    /// Defines a symbol in this symbol store, checking for conflicts with existing definitions.
+    ///
+    /// Returns `Ok(None)` if the symbol was defined without a reported conflict, or
+    /// `Ok(Some(...))` if the symbol was defined despite a conflict under a `Warn` policy.
+    /// Redefinition is checked before shadowing, and only one conflict is reported.
+    ///
+    /// # Failures
+    /// Fails (without defining the symbol) if a conflict occurs under an `Error` policy.
+    fn define_checked(&mut self, ident: Identifier, symbol: Sym)
+        -> Result<Option<DefineConflict<Sym>>, DefineConflict<Sym>>;
+}
+
+impl<Sym, Tbl> DefineStore<Sym> for Scope<Tbl>
+    where Sym: Clone + HasSpan, Tbl: SymbolTable<Sym>
+{
+    fn define_checked(&mut self, ident: Identifier, symbol: Sym)
+        -> Result<Option<DefineConflict<Sym>>, DefineConflict<Sym>>
+    {
+        let existing = match self.item.symbol_get(&ident) {
+            Some(previous) => Some((ConflictKind::Redefinition, previous.clone(),
+                self.define_policy.redefinition)),
+            None => match self.parent {
+                Some(ref parent) => parent.borrow().resolve(&ident).map(|previous| {
+                    (ConflictKind::Shadowing, previous, self.define_policy.shadowing)
+                }),
+                None => None,
+            }
+        };
+        let conflict = match existing {
+            Some((_, _, ConflictPolicy::Allow)) | None => None,
+            Some((kind, previous, policy)) => {
+                let conflict = DefineConflict {
+                    kind,
+                    ident,
+                    previous_span: previous.span(),
+                    previous,
+                };
+                if policy == ConflictPolicy::Error {
+                    return Err(conflict);
+                }
+                Some(conflict)
+            }
+        };
+        self.define(ident, symbol);
+        Ok(conflict)
+    }
+}
@@ -0,0 +1,260 @@
+//! Serialisation of scopes (requires the `serde` feature).
+//!
+//! Scopes are linked to their parents, base scopes and imported namespaces through shared `Rc`
+//! pointers, and symbols may hold further shared scopes (e.g. the member scope of a class symbol,
+//! whose parent links back to the scope defining the class). A single scope may therefore be
+//! reachable through several paths, including cycles.
+//!
+//! While a scope is being serialised, every shared scope reachable from it is recorded in a single
+//! registry: the first occurrence of a shared scope is serialised in full along with a new index,
+//! and every later occurrence as the index alone. Deserialising rebuilds the same sharing
+//! structure (including cycles). Links held by the scopes themselves use the registry
+//! automatically; shared scopes held by symbols must opt in by serialising through this module,
+//! e.g. with `#[serde(with = "sindra::scope::serialize")]` on an `Rc<RefCell<Scope<T>>>` field.
+//!
+//! A scope serialised by value (rather than through a shared pointer) cannot be referred to by the
+//! scopes reachable from it; use `Shared` to serialise such a scope.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::thread::LocalKey;
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::{self, SerializeTuple};
+
+use scope::{Scope, Import, DefinePolicy, ScopeKind, Capture};
+use Identifier;
+
+type SharedScope<T> = Rc<RefCell<Scope<T>>>;
+
+/// Scopes serialised so far in the current session, keyed by address: the index allocated to the
+/// scope, and whether it was serialised by value (and so cannot be referred to).
+type SerRegistry = HashMap<usize, (usize, bool)>;
+/// Shared scopes deserialised so far in the current session, keyed by index.
+type DeRegistry = HashMap<usize, Rc<dyn Any>>;
+
+thread_local! {
+    static SERIALIZED: RefCell<Option<SerRegistry>> = const { RefCell::new(None) };
+    static DESERIALIZED: RefCell<Option<DeRegistry>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` within a (de)serialisation session, starting a new session (which ends when `f`
+/// returns) if one is not already active.
+fn in_session<M: Default + 'static, R, F: FnOnce() -> R>(
+    registry: &'static LocalKey<RefCell<Option<M>>>, f: F) -> R
+{
+    struct End<M: 'static>(&'static LocalKey<RefCell<Option<M>>>);
+    impl<M> Drop for End<M> {
+        fn drop(&mut self) { self.0.with(|registry| *registry.borrow_mut() = None); }
+    }
+    let started = registry.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        if registry.is_some() {
+            return false;
+        }
+        *registry = Some(M::default());
+        true
+    });
+    let _end = if started { Some(End(registry)) } else { None };
+    f()
+}
+
+/// Registers a scope (by address) in the current serialisation session, returning its index and
+/// whether it had already been registered.
+fn register(address: usize, by_value: bool) -> (usize, Option<bool>) {
+    SERIALIZED.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        let registry = registry.as_mut().expect("no active serialisation session");
+        if let Some(&(index, existing_by_value)) = registry.get(&address) {
+            return (index, Some(existing_by_value));
+        }
+        let index = registry.len();
+        registry.insert(address, (index, by_value));
+        (index, None)
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+enum ImportData<L> {
+    Name { namespace: L, ident: Identifier, alias: Identifier },
+    Glob(L),
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScopeData<I, L> {
+    item: I,
+    parent: Option<L>,
+    bases: Vec<L>,
+    imports: Vec<ImportData<L>>,
+    define_policy: DefinePolicy,
+    kind: ScopeKind,
+    captures: Vec<Capture>,
+}
+
+/// Borrowed link to a shared scope, serialised through the registry.
+struct LinkRef<'a, T: 'a>(&'a SharedScope<T>);
+impl<'a, T: Serialize> Serialize for LinkRef<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self.0, serializer)
+    }
+}
+
+fn scope_data<T>(scope: &Scope<T>) -> ScopeData<&T, LinkRef<'_, T>> {
+    ScopeData {
+        item: &scope.item,
+        parent: scope.parent.as_ref().map(LinkRef),
+        bases: scope.bases.iter().map(LinkRef).collect(),
+        imports: scope.imports.iter().map(|import| match *import {
+            Import::Name { ref namespace, ident, alias } => {
+                ImportData::Name { namespace: LinkRef(namespace), ident, alias }
+            },
+            Import::Glob(ref namespace) => ImportData::Glob(LinkRef(namespace)),
+        }).collect(),
+        define_policy: scope.define_policy,
+        kind: scope.kind,
+        captures: scope.captures.clone(),
+    }
+}
+
+impl<T> ScopeData<T, Link<T>> {
+    fn into_scope(self) -> Scope<T> {
+        Scope {
+            item: self.item,
+            parent: self.parent.map(|link| link.0),
+            bases: self.bases.into_iter().map(|link| link.0).collect(),
+            imports: self.imports.into_iter().map(|import| match import {
+                ImportData::Name { namespace, ident, alias } => {
+                    Import::Name { namespace: namespace.0, ident, alias }
+                },
+                ImportData::Glob(namespace) => Import::Glob(namespace.0),
+            }).collect(),
+            define_policy: self.define_policy,
+            kind: self.kind,
+            captures: self.captures,
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for Scope<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        in_session(&SERIALIZED, || {
+            let address = self as *const Scope<T> as usize;
+            if register(address, true).1.is_some() {
+                return Err(ser::Error::custom("scope serialised by value more than once (shared \
+                    scopes held by symbols must be serialised with `sindra::scope::serialize`)"));
+            }
+            scope_data(self).serialize(serializer)
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Scope<T> where T: Deserialize<'de> + Default + 'static {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Scope<T>, D::Error> {
+        in_session(&DESERIALIZED, || {
+            ScopeData::<T, Link<T>>::deserialize(deserializer).map(ScopeData::into_scope)
+        })
+    }
+}
+
+/// Serialises a shared scope through the registry of the current serialisation session (see the
+/// module documentation). Suitable for use with `#[serde(serialize_with = "...")]`.
+pub fn serialize<T, S>(scope: &Rc<RefCell<Scope<T>>>, serializer: S) -> Result<S::Ok, S::Error>
+    where T: Serialize, S: Serializer
+{
+    in_session(&SERIALIZED, || {
+        // the address of the scope itself (rather than the `RefCell`) is used, so that scopes
+        // serialised by value are recognised
+        let (index, existing) = register(scope.as_ptr() as usize, false);
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&index)?;
+        match existing {
+            Some(true) => {
+                return Err(ser::Error::custom("shared scope refers to a scope serialised by \
+                    value (serialise the outer scope as `Shared` instead)"));
+            },
+            Some(false) => tuple.serialize_element(&None::<()>)?,
+            None => {
+                let borrowed = scope.try_borrow()
+                    .map_err(|_| ser::Error::custom("scope is mutably borrowed"))?;
+                tuple.serialize_element(&Some(scope_data(&borrowed)))?;
+            }
+        }
+        tuple.end()
+    })
+}
+
+/// Deserialises a shared scope through the registry of the current deserialisation session (see
+/// the module documentation). Suitable for use with `#[serde(deserialize_with = "...")]`.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Rc<RefCell<Scope<T>>>, D::Error>
+    where T: Deserialize<'de> + Default + 'static, D: Deserializer<'de>
+{
+    Link::deserialize(deserializer).map(|link| link.0)
+}
+
+/// Owned link to a shared scope, deserialised through the registry.
+struct Link<T>(SharedScope<T>);
+impl<'de, T> Deserialize<'de> for Link<T> where T: Deserialize<'de> + Default + 'static {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Link<T>, D::Error> {
+        in_session(&DESERIALIZED, || deserializer.deserialize_tuple(2, LinkVisitor(PhantomData)))
+    }
+}
+
+struct LinkVisitor<T>(PhantomData<T>);
+impl<'de, T> Visitor<'de> for LinkVisitor<T> where T: Deserialize<'de> + Default + 'static {
+    type Value = Link<T>;
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a shared scope index and optional scope")
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Link<T>, A::Error> {
+        let index: usize = seq.next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let existing = DESERIALIZED.with(|registry| {
+            registry.borrow().as_ref().and_then(|registry| registry.get(&index).cloned())
+        });
+        if let Some(existing) = existing {
+            seq.next_element::<Option<de::IgnoredAny>>()?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            let scope = existing.downcast::<RefCell<Scope<T>>>()
+                .map_err(|_| de::Error::custom(format!("mismatched scope type at {}", index)))?;
+            return Ok(Link(scope));
+        }
+        // register the scope before deserialising its contents, which may refer back to it
+        let scope: SharedScope<T> = Rc::new(RefCell::new(Scope::default()));
+        DESERIALIZED.with(|registry| {
+            let mut registry = registry.borrow_mut();
+            let registry = registry.as_mut().expect("no active deserialisation session");
+            registry.insert(index, Rc::clone(&scope) as Rc<dyn Any>);
+        });
+        match seq.next_element::<Option<ScopeData<T, Link<T>>>>()? {
+            Some(Some(data)) => {
+                *scope.borrow_mut() = data.into_scope();
+                Ok(Link(scope))
+            },
+            Some(None) => Err(de::Error::custom(format!("unknown scope index: {}", index))),
+            None => Err(de::Error::invalid_length(1, &self)),
+        }
+    }
+}
+
+/// Shared scope which is serialised (with all scopes reachable from it) through the registry, so
+/// that scopes reachable from it may refer back to it.
+///
+/// Use this to serialise a scope which defines symbols holding scopes whose parent is the defining
+/// scope (e.g. a global scope containing class or namespace symbols).
+#[derive(Debug, Clone)]
+pub struct Shared<T>(pub Rc<RefCell<Scope<T>>>);
+impl<T: Serialize> Serialize for Shared<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+impl<'de, T> Deserialize<'de> for Shared<T> where T: Deserialize<'de> + Default + 'static {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Shared<T>, D::Error> {
+        deserialize(deserializer).map(Shared)
+    }
+}
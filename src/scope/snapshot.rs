@@ -0,0 +1,170 @@
+//! Memory snapshot and diffing implementation.
+//!
+//! A `MemorySnapshot` captures the bindings (symbols and values) of a `MemoryScope` and all its
+//! ancestors at a point in time, as plain owned data. Two snapshots can be compared with
+//! `MemorySnapshot::diff` to find which bindings were added, removed or changed between them,
+//! which is useful when debugging an interpreter.
+
+use std::fmt::{self, Display};
+
+use scope::{MemoryScope, SymbolMemory};
+use Identifier;
+
+/// Snapshot of the bindings of a `MemoryScope` chain.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MemorySnapshot<Sym, Val> {
+    /// Bindings of each scope in the chain, in slot order, from the root (global) scope at level
+    /// 0 to the innermost scope.
+    pub levels: Vec<Vec<(Identifier, SymbolMemory<Sym, Val>)>>,
+}
+impl<Sym: Clone, Val: Clone> MemorySnapshot<Sym, Val> {
+    /// Captures a snapshot of the bindings of a scope and all its ancestors.
+    pub fn new(scope: &MemoryScope<Sym, Val>) -> MemorySnapshot<Sym, Val> {
+        let mut levels = vec![bindings(scope)];
+        let mut parent = scope.parent.clone();
+        while let Some(current) = parent {
+            let current = current.borrow();
+            levels.push(bindings(&current));
+            parent = current.parent.clone();
+        }
+        levels.reverse();
+        MemorySnapshot { levels }
+    }
+}
+fn bindings<Sym: Clone, Val: Clone>(scope: &MemoryScope<Sym, Val>)
+    -> Vec<(Identifier, SymbolMemory<Sym, Val>)>
+{
+    scope.item.iter().map(|(ident, sm)| (*ident, sm.clone())).collect()
+}
+impl<Sym: Clone + PartialEq, Val: Clone + PartialEq> MemorySnapshot<Sym, Val> {
+    /// Computes the changes from this snapshot to a later snapshot `other`. Scopes are matched by
+    /// level (distance from the root scope).
+    pub fn diff(&self, other: &MemorySnapshot<Sym, Val>) -> MemoryDiff<Sym, Val> {
+        let empty = vec![];
+        let mut changes = vec![];
+        for level in 0..self.levels.len().max(other.levels.len()) {
+            let before = self.levels.get(level).unwrap_or(&empty);
+            let after = other.levels.get(level).unwrap_or(&empty);
+            for (ident, old) in before {
+                match after.iter().find(|(other_ident, _)| other_ident == ident) {
+                    Some((_, new)) if new != old => {
+                        changes.push(MemoryChange::Changed {
+                            level,
+                            ident: *ident,
+                            old: old.clone(),
+                            new: new.clone(),
+                        });
+                    },
+                    Some(_) => {},
+                    None => {
+                        changes.push(MemoryChange::Removed {
+                            level,
+                            ident: *ident,
+                            old: old.clone(),
+                        });
+                    }
+                }
+            }
+            for (ident, new) in after {
+                if !before.iter().any(|(other_ident, _)| other_ident == ident) {
+                    changes.push(MemoryChange::Added {
+                        level,
+                        ident: *ident,
+                        new: new.clone(),
+                    });
+                }
+            }
+        }
+        MemoryDiff { changes }
+    }
+}
+impl<Sym: Display, Val: Display> Display for MemorySnapshot<Sym, Val> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        for (level, bindings) in self.levels.iter().enumerate() {
+            for (ident, sm) in bindings {
+                writeln!(f, "[{}] {}: {}", level, ident, sm)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Single change to a binding between two snapshots.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MemoryChange<Sym, Val> {
+    /// The binding was added.
+    Added {
+        /// Level of the scope containing the binding.
+        level: usize,
+        /// Identifier of the binding.
+        ident: Identifier,
+        /// New symbol and value.
+        new: SymbolMemory<Sym, Val>,
+    },
+    /// The binding was removed.
+    Removed {
+        /// Level of the scope which contained the binding.
+        level: usize,
+        /// Identifier of the binding.
+        ident: Identifier,
+        /// Previous symbol and value.
+        old: SymbolMemory<Sym, Val>,
+    },
+    /// The binding's symbol, value or mutability changed.
+    Changed {
+        /// Level of the scope containing the binding.
+        level: usize,
+        /// Identifier of the binding.
+        ident: Identifier,
+        /// Previous symbol and value.
+        old: SymbolMemory<Sym, Val>,
+        /// New symbol and value.
+        new: SymbolMemory<Sym, Val>,
+    },
+}
+impl<Sym: Display, Val: Display> Display for MemoryChange<Sym, Val> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        match *self {
+            MemoryChange::Added { level, ref ident, ref new } => {
+                write!(f, "+ [{}] {}: {}", level, ident, new)
+            },
+            MemoryChange::Removed { level, ref ident, ref old } => {
+                write!(f, "- [{}] {}: {}", level, ident, old)
+            },
+            MemoryChange::Changed { level, ref ident, ref old, ref new } => {
+                write!(f, "~ [{}] {}: {}", level, ident, old)?;
+                // show mutability only if it changed (otherwise the change would be invisible if
+                // the value remained the same)
+                if old.mutability() != new.mutability() {
+                    write!(f, " ({}) -> {} ({})", old.mutability(), new, new.mutability())
+                } else {
+                    write!(f, " -> {}", new)
+                }
+            }
+        }
+    }
+}
+
+/// Set of changes between two memory snapshots (see `MemorySnapshot::diff`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MemoryDiff<Sym, Val> {
+    /// Changes, ordered by scope level.
+    pub changes: Vec<MemoryChange<Sym, Val>>,
+}
+impl<Sym, Val> MemoryDiff<Sym, Val> {
+    /// Returns `true` if there are no changes.
+    pub fn is_empty(&self) -> bool { self.changes.is_empty() }
+    /// Number of changes.
+    pub fn len(&self) -> usize { self.changes.len() }
+}
+impl<Sym: Display, Val: Display> Display for MemoryDiff<Sym, Val> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
@@ -1,12 +1,75 @@
 //! Symbol table implementation and scope specification.
 
-use std::collections::HashMap;
+use std::cmp::{max, min};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 
-use scope::Scope;
-use Identifier;
+use scope::{Scope, Scoped, MemberStore};
+use {Identifier, IdentPath};
 
 /// Scope specialization for a symbol table using symbol type `Sym`.
-pub type SymbolScope<Sym> = Scope<HashMap<Identifier, Sym>>;
+pub type SymbolScope<Sym> = Scope<SlotTable<Sym>>;
+
+/// Table of values keyed by identifier, which allocates each identifier a slot index (in order of
+/// first definition) that remains stable when the identifier's value is updated.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SlotTable<V> {
+    slots: HashMap<Identifier, usize>,
+    entries: Vec<(Identifier, V)>,
+}
+impl<V> Default for SlotTable<V> {
+    fn default() -> SlotTable<V> {
+        SlotTable {
+            slots: HashMap::new(),
+            entries: vec![],
+        }
+    }
+}
+impl<V> SlotTable<V> {
+    /// Creates a new, empty table.
+    pub fn new() -> SlotTable<V> { SlotTable::default() }
+
+    /// Inserts (or updates) the value for an identifier, allocating a new slot if the identifier
+    /// is not already in the table. Returns the previous value (if exists).
+    pub fn insert(&mut self, ident: Identifier, value: V) -> Option<V> {
+        match self.slots.get(&ident) {
+            Some(&slot) => Some(::std::mem::replace(&mut self.entries[slot].1, value)),
+            None => {
+                self.slots.insert(ident, self.entries.len());
+                self.entries.push((ident, value));
+                None
+            }
+        }
+    }
+    /// Retrieves the value for an identifier.
+    pub fn get(&self, ident: &Identifier) -> Option<&V> {
+        self.slots.get(ident).map(|&slot| &self.entries[slot].1)
+    }
+    /// Retrieves a mutable reference to the value for an identifier.
+    pub fn get_mut(&mut self, ident: &Identifier) -> Option<&mut V> {
+        match self.slots.get(ident) {
+            Some(&slot) => Some(&mut self.entries[slot].1),
+            None => None,
+        }
+    }
+    /// Slot index allocated to an identifier.
+    pub fn slot(&self, ident: &Identifier) -> Option<usize> {
+        self.slots.get(ident).cloned()
+    }
+    /// Retrieves the identifier and value in a slot.
+    pub fn get_slot(&self, slot: usize) -> Option<(&Identifier, &V)> {
+        self.entries.get(slot).map(|(ident, value)| (ident, value))
+    }
+    /// Iterates over the identifiers and values in this table, in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Identifier, &V)> {
+        self.entries.iter().map(|(ident, value)| (ident, value))
+    }
+    /// Number of slots allocated in this table.
+    pub fn len(&self) -> usize { self.entries.len() }
+    /// Returns `true` if no slots have been allocated in this table.
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+}
 
 /// Methods for setting and retrieving symbols of type `Sym` from a symbol table.
 pub trait SymbolTable<Sym> {
@@ -15,17 +78,144 @@ pub trait SymbolTable<Sym> {
     fn symbol_set(&mut self, ident: Identifier, symbol: Sym) -> Option<Sym>;
     /// Retrieves the symbol for an identifier, or `None` if no symbol exists for that identifier.
     fn symbol_get(&self, ident: &Identifier) -> Option<&Sym>;
+    /// Retrieves the slot index allocated to an identifier. Slot indices are stable: redefining
+    /// an identifier does not change its slot. Returns `None` if no symbol exists for that
+    /// identifier, or if this table does not allocate slots.
+    fn symbol_slot(&self, _ident: &Identifier) -> Option<usize> { None }
+    /// Iterates over the identifiers and symbols in this symbol table. Tables which do not
+    /// support iteration yield no symbols.
+    fn symbols(&self) -> Box<dyn Iterator<Item = (&Identifier, &Sym)> + '_> {
+        Box::new(::std::iter::empty())
+    }
+}
+
+/// Static location of a resolved symbol, relative to the scope of the lookup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SymbolLocation {
+    /// Number of parent hops from the scope of the lookup to the scope defining the symbol.
+    pub depth: usize,
+    /// Slot index of the symbol within the defining scope.
+    pub slot: usize,
+}
+
+/// Error resulting from a failed symbol resolution.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveError {
+    /// No symbol exists for the identifier.
+    Undefined(Identifier),
+    /// More than one import provides a symbol for the identifier.
+    Ambiguous(Identifier),
+    /// A symbol exists for the identifier, but is not accessible from the scope of the lookup.
+    Inaccessible(Identifier),
+    /// The path to resolve contains no identifiers.
+    EmptyPath,
+}
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        match *self {
+            ResolveError::Undefined(ref ident) => write!(f, "undefined name: {}", ident),
+            ResolveError::Ambiguous(ref ident) => write!(f, "ambiguous name: {}", ident),
+            ResolveError::Inaccessible(ref ident) => write!(f, "inaccessible name: {}", ident),
+            ResolveError::EmptyPath => write!(f, "empty path"),
+        }
+    }
 }
 
 /// Methods for defining and resolving symbols of type `Sym`. Similar to `SymbolTable`, but
 /// intended for use when a search (possibly through multiple scopes) is required to find a
 /// particular symbol.
+///
+/// Scopes resolve identifiers hygienically: an identifier with no symbol defined for it in its own
+/// syntax context resolves in the outer context, and so on up to the root context (see
+/// `SyntaxContext`).
 pub trait SymbolStore<Sym> {
     /// Defines (or updates) a symbol in this symbol store. Returns previous symbol if this
     /// identifier has been previously declared.
+    ///
+    /// Conflicting definitions are always allowed; see `DefineStore::define_checked` for
+    /// definitions subject to a scope's `DefinePolicy`.
     fn define(&mut self, ident: Identifier, symbol: Sym) -> Option<Sym>;
     /// Resolves a symbol in this symbol store, or `None` if no symbol exists for the identifier.
     fn resolve(&self, ident: &Identifier) -> Option<Sym>;
+    /// Resolves a symbol in this symbol store, reporting the reason for failure.
+    ///
+    /// # Failures
+    /// Fails with `ResolveError::Undefined` if no symbol exists for the identifier, or another
+    /// `ResolveError` if the identifier cannot be unambiguously resolved.
+    fn try_resolve(&self, ident: &Identifier) -> Result<Sym, ResolveError> {
+        self.resolve(ident).ok_or(ResolveError::Undefined(*ident))
+    }
+    /// Resolves a symbol in this symbol store along with its static location, or `None` if no
+    /// symbol exists for the identifier or the symbol has no static location (e.g. if it is
+    /// provided by a base scope or an import, or the defining table does not allocate slots).
+    fn resolve_with_location(&self, _ident: &Identifier) -> Option<(Sym, SymbolLocation)> {
+        None
+    }
+    /// Lists all symbols visible from this symbol store, with symbols shadowed by another symbol
+    /// of the same identifier omitted. Stores which do not support enumeration list no symbols.
+    fn visible_symbols(&self) -> Vec<(Identifier, Sym)> { vec![] }
+    /// Suggests visible identifiers similar to `ident` (e.g. for "did you mean" diagnostics after
+    /// a failed resolution), ordered from most to least similar.
+    fn suggest(&self, ident: &Identifier) -> Vec<Identifier> {
+        suggest(ident, self.visible_symbols().into_iter().map(|(ident, _)| ident))
+    }
+    /// Resolves a qualified name (e.g. `std::io::Write` or `obj.field`, however the path was
+    /// parsed) in this symbol store, one segment at a time: the first segment is resolved from
+    /// this store, and each subsequent segment as a member of the namespace or member scope of the
+    /// symbol resolved for the previous segment (see `MemberStore::resolve_path`).
+    ///
+    /// Returns `None` if any segment fails to resolve, or any non-final segment's symbol has no
+    /// member scope.
+    fn resolve_qualified(&self, path: &IdentPath) -> Option<Sym>
+        where Sym: Scoped<Self>, Self: MemberStore<Sym> + Default + Sized
+    {
+        self.resolve_path(path.segments())
+    }
+}
+
+/// Selects candidate identifiers similar to `ident`, ordered from most to least similar (ties
+/// broken alphabetically). A candidate is similar if its edit distance from `ident` is at most one
+/// third of the length of `ident` (but at least one). Candidates with the same text as `ident` are
+/// never suggested, and of several candidates with the same text (e.g. in different syntax
+/// contexts) only the first is suggested.
+pub fn suggest<I>(ident: &Identifier, candidates: I) -> Vec<Identifier>
+    where I: IntoIterator<Item = Identifier>
+{
+    let max_distance = max(1, ident.as_str().chars().count() / 3);
+    let mut seen = HashSet::new();
+    let mut similar = candidates.into_iter()
+        .filter(|candidate| candidate.as_str() != ident.as_str() && seen.insert(candidate.as_str()))
+        .map(|candidate| (edit_distance(ident.as_str(), candidate.as_str()), candidate))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .collect::<Vec<_>>();
+    similar.sort_by(|left, right| {
+        left.0.cmp(&right.0).then_with(|| (left.1).as_str().cmp((right.1).as_str()))
+    });
+    similar.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+/// Computes the edit distance (in characters) between two strings: the number of insertions,
+/// deletions, substitutions, or transpositions of adjacent characters required to turn one into
+/// the other (the "optimal string alignment" distance).
+pub fn edit_distance(left: &str, right: &str) -> usize {
+    let left = left.chars().collect::<Vec<_>>();
+    let right = right.chars().collect::<Vec<_>>();
+    // dist[i][j] is the distance between the first i characters of left and first j of right
+    let mut dist = vec![vec![0; right.len() + 1]; left.len() + 1];
+    for (i, row) in dist.iter_mut().enumerate() { row[0] = i; }
+    for (j, cell) in dist[0].iter_mut().enumerate() { *cell = j; }
+    for i in 1..=left.len() {
+        for j in 1..=right.len() {
+            let cost = if left[i - 1] == right[j - 1] { 0 } else { 1 };
+            let mut best = min(dist[i - 1][j - 1] + cost, min(dist[i - 1][j], dist[i][j - 1]) + 1);
+            if i > 1 && j > 1 && left[i - 1] == right[j - 2] && left[i - 2] == right[j - 1] {
+                best = min(best, dist[i - 2][j - 2] + 1);
+            }
+            dist[i][j] = best;
+        }
+    }
+    dist[left.len()][right.len()]
 }
 
 impl<Sym> SymbolTable<Sym> for HashMap<Identifier, Sym> {
@@ -33,24 +223,100 @@ impl<Sym> SymbolTable<Sym> for HashMap<Identifier, Sym> {
         self.insert(ident, symbol)
     }
     fn symbol_get(&self, ident: &Identifier) -> Option<&Sym> { self.get(ident) }
+    fn symbols(&self) -> Box<dyn Iterator<Item = (&Identifier, &Sym)> + '_> {
+        Box::new(self.iter())
+    }
+}
+impl<Sym> SymbolTable<Sym> for SlotTable<Sym> {
+    fn symbol_set(&mut self, ident: Identifier, symbol: Sym) -> Option<Sym> {
+        self.insert(ident, symbol)
+    }
+    fn symbol_get(&self, ident: &Identifier) -> Option<&Sym> { self.get(ident) }
+    fn symbol_slot(&self, ident: &Identifier) -> Option<usize> { self.slot(ident) }
+    fn symbols(&self) -> Box<dyn Iterator<Item = (&Identifier, &Sym)> + '_> {
+        Box::new(self.iter())
+    }
 }
 impl<Sym: Clone, Tbl> SymbolStore<Sym> for Scope<Tbl> where Tbl: SymbolTable<Sym> {
     fn define(&mut self, ident: Identifier, symbol: Sym) -> Option<Sym> {
         self.item.symbol_set(ident, symbol)
     }
     fn resolve(&self, ident: &Identifier) -> Option<Sym> {
-        match self.item.symbol_get(ident) {
-            Some(&ref symbol) => Some(symbol.clone()),
-            None => {
-                match self.parent {
-                    Some(ref parent) => {
-                        parent.borrow().resolve(ident)
-                    },
-                    None => None
-                }
-            }
+        self.try_resolve(ident).ok()
+    }
+    fn try_resolve(&self, ident: &Identifier) -> Result<Sym, ResolveError> {
+        resolve_outward(ident, |candidate| self.try_resolve_exact(candidate))
+    }
+    fn resolve_with_location(&self, ident: &Identifier) -> Option<(Sym, SymbolLocation)> {
+        let binding = resolve_outward(ident, |candidate| {
+            self.try_resolve_exact::<Sym>(candidate).map(|_| *candidate)
+        }).ok()?;
+        self.resolve_with_location_exact(&binding)
+    }
+    fn visible_symbols(&self) -> Vec<(Identifier, Sym)> {
+        let mut candidates = self.member_symbols();
+        candidates.extend(self.imported_symbols());
+        if let Some(ref parent) = self.parent {
+            candidates.extend(parent.borrow().visible_symbols());
+        }
+        // earlier candidates shadow later ones
+        let mut seen = HashSet::new();
+        candidates.into_iter().filter(|(ident, _)| seen.insert(*ident)).collect()
+    }
+}
+
+impl<Tbl> Scope<Tbl> {
+    /// Resolves a symbol defined for exactly this identifier (without stripping marks).
+    fn try_resolve_exact<Sym: Clone>(&self, ident: &Identifier) -> Result<Sym, ResolveError>
+        where Tbl: SymbolTable<Sym>
+    {
+        if let Some(symbol) = self.resolve_member(ident) {
+            return Ok(symbol);
+        }
+        if let Some(symbol) = self.resolve_import(ident)? {
+            return Ok(symbol);
+        }
+        match self.parent {
+            Some(ref parent) => {
+                parent.borrow().try_resolve_exact(ident)
+            },
+            None => Err(ResolveError::Undefined(*ident))
+        }
+    }
+    fn resolve_with_location_exact<Sym: Clone>(&self, ident: &Identifier)
+        -> Option<(Sym, SymbolLocation)>
+        where Tbl: SymbolTable<Sym>
+    {
+        if let Some(symbol) = self.item.symbol_get(ident) {
+            let slot = self.item.symbol_slot(ident)?;
+            return Some((symbol.clone(), SymbolLocation { depth: 0, slot }));
+        }
+        // symbols provided by base scopes or imports have no static location
+        if self.resolve_member(ident).is_some()
+            || !matches!(self.resolve_import::<Sym>(ident), Ok(None))
+        {
+            return None;
+        }
+        let parent = self.parent.as_ref()?;
+        let (symbol, location) = parent.borrow().resolve_with_location_exact(ident)?;
+        Some((symbol, SymbolLocation { depth: location.depth + 1, ..location }))
+    }
+}
+
+/// Resolves an identifier using `resolve_exact`, stripping marks outward (see `SyntaxContext`)
+/// for as long as no symbol is defined for the identifier. Failures other than
+/// `ResolveError::Undefined` end the search.
+pub(crate) fn resolve_outward<T, F>(ident: &Identifier, mut resolve_exact: F)
+    -> Result<T, ResolveError>
+    where F: FnMut(&Identifier) -> Result<T, ResolveError>
+{
+    for candidate in ident.outward() {
+        match resolve_exact(&candidate) {
+            Err(ResolveError::Undefined(_)) => {},
+            result => return result,
         }
     }
+    Err(ResolveError::Undefined(*ident))
 }
 
 // #[cfg(test)]
@@ -105,8 +371,8 @@ impl<Sym: Clone, Tbl> SymbolStore<Sym> for Scope<Tbl> where Tbl: SymbolTable<Sym
 //     fn test_define() {
 //         let elem = "hello".to_string();
 //         let mut foo = Ast::<String>::new(elem);
-//         foo.define(Identifier("ident".to_string()),
-//             Symbol::Variable(Identifier("ident".to_string()), None));
+//         foo.define(Identifier::new("ident"),
+//             Symbol::Variable(Identifier::new("ident"), None));
 //         println!("{}", foo);
 //     }
 // }
@@ -0,0 +1,155 @@
+//! Scope tree retention.
+//!
+//! `Scope` objects are only linked from child to parent, so once analysis finishes there is no
+//! way to enumerate scopes or find the children of a scope. A `ScopeTree` can optionally be used
+//! to record scopes as they are created, along with the syntax tree node (identified by a
+//! user-chosen key of type `N`) and source span that introduced each scope, for later queries
+//! (e.g. finding the scope enclosing a source position for IDE completion).
+
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use scope::{Scope, Stack};
+use span::Span;
+
+/// Identifier for a scope recorded in a `ScopeTree`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ScopeId(pub usize);
+
+/// Scope recorded in a `ScopeTree`.
+#[derive(Debug, Clone)]
+pub struct ScopeEntry<T, N> {
+    /// The recorded scope.
+    pub scope: Rc<RefCell<Scope<T>>>,
+    /// Parent of the scope in the tree: the nearest recorded ancestor of the scope (following
+    /// `Scope::parent` links), if any.
+    pub parent: Option<ScopeId>,
+    /// Children of the scope in the tree, in order of creation.
+    pub children: Vec<ScopeId>,
+    /// Key of the syntax tree node that introduced the scope.
+    pub node: N,
+    /// Location in the source of the region covered by the scope (if known).
+    pub span: Option<Span>,
+    /// Depth of the scope in the tree (zero for root scopes). Ancestors of the scope which were
+    /// never recorded are not counted.
+    pub depth: usize,
+}
+
+/// Tree of scopes, recording parent-to-child links along with the syntax tree node and span
+/// associated with each scope.
+#[derive(Debug, Clone)]
+pub struct ScopeTree<T, N> {
+    entries: Vec<ScopeEntry<T, N>>,
+    ids: HashMap<*const RefCell<Scope<T>>, ScopeId>,
+}
+impl<T, N> Default for ScopeTree<T, N> {
+    fn default() -> ScopeTree<T, N> {
+        ScopeTree {
+            entries: vec![],
+            ids: HashMap::new(),
+        }
+    }
+}
+impl<T, N> ScopeTree<T, N> {
+    /// Creates a new, empty scope tree.
+    pub fn new() -> ScopeTree<T, N> { ScopeTree::default() }
+
+    /// Records an existing scope in the tree. The nearest already-recorded ancestor of the scope
+    /// (if any) is linked as its tree parent. Returns the existing identifier if the scope has
+    /// already been recorded.
+    pub fn record(&mut self, scope: &Rc<RefCell<Scope<T>>>, node: N, span: Option<Span>)
+        -> ScopeId
+    {
+        if let Some(id) = self.id_of(scope) {
+            return id;
+        }
+        let mut parent = None;
+        let mut ancestor = scope.borrow().parent.clone();
+        while let Some(current) = ancestor {
+            parent = self.id_of(&current);
+            if parent.is_some() {
+                break;
+            }
+            ancestor = current.borrow().parent.clone();
+        }
+        let id = ScopeId(self.entries.len());
+        let depth = match parent {
+            Some(parent) => {
+                self.entries[parent.0].children.push(id);
+                self.entries[parent.0].depth + 1
+            },
+            None => 0,
+        };
+        self.entries.push(ScopeEntry {
+            scope: Rc::clone(scope),
+            parent,
+            children: vec![],
+            node,
+            span,
+            depth,
+        });
+        self.ids.insert(Rc::as_ptr(scope), id);
+        id
+    }
+
+    /// Pushes a new scope as a child of `parent` (see `Stack::push`) and records it in the tree.
+    pub fn push(&mut self, parent: &Rc<RefCell<Scope<T>>>, node: N, span: Option<Span>)
+        -> Rc<RefCell<Scope<T>>>
+        where T: Default + PartialEq
+    {
+        let scope = parent.push();
+        self.record(&scope, node, span);
+        scope
+    }
+
+    /// Identifier of a recorded scope.
+    pub fn id_of(&self, scope: &Rc<RefCell<Scope<T>>>) -> Option<ScopeId> {
+        self.ids.get(&Rc::as_ptr(scope)).cloned()
+    }
+
+    /// Accesses a recorded scope entry.
+    pub fn get(&self, id: ScopeId) -> Option<&ScopeEntry<T, N>> { self.entries.get(id.0) }
+
+    /// Accesses a recorded scope.
+    pub fn scope(&self, id: ScopeId) -> Option<&Rc<RefCell<Scope<T>>>> {
+        self.get(id).map(|entry| &entry.scope)
+    }
+
+    /// Children of a recorded scope, in order of creation.
+    pub fn children(&self, id: ScopeId) -> &[ScopeId] {
+        self.get(id).map_or(&[], |entry| &entry.children[..])
+    }
+
+    /// Recorded scopes which have no recorded parent.
+    pub fn roots(&self) -> impl Iterator<Item = ScopeId> + '_ {
+        self.entries.iter().enumerate()
+            .filter(|&(_, entry)| entry.parent.is_none())
+            .map(|(index, _)| ScopeId(index))
+    }
+
+    /// Iterates over all recorded scope entries, in order of recording.
+    pub fn iter(&self) -> impl Iterator<Item = (ScopeId, &ScopeEntry<T, N>)> {
+        self.entries.iter().enumerate().map(|(index, entry)| (ScopeId(index), entry))
+    }
+
+    /// Number of recorded scopes.
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    /// Returns `true` if no scopes have been recorded.
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    /// Finds the innermost recorded scope whose span contains the source position `pos`: the
+    /// scope with the smallest such span (preferring the deepest scope if several have the same
+    /// span).
+    pub fn enclosing(&self, pos: usize) -> Option<ScopeId> {
+        self.iter()
+            .filter_map(|(id, entry)| match entry.span {
+                Some(span) if span.contains(pos) => Some((id, span.len(), entry.depth)),
+                _ => None,
+            })
+            .min_by_key(|&(_, len, depth)| (len, Reverse(depth)))
+            .map(|(id, _, _)| id)
+    }
+}
@@ -0,0 +1,119 @@
+//! Symbol visibility and access control.
+//!
+//! Symbols found through the lexical parent chain of a scope are always accessible from that
+//! scope. Symbols found as members of another scope (through base scopes, imports, or member
+//! paths) are accessible only if they are public, or if the scope of the lookup is lexically
+//! within the scope that defines them.
+
+use std::cell::RefCell;
+use std::ptr;
+use std::rc::Rc;
+
+use scope::{Scope, Scoped, ResolveError};
+use scope::member::find_member;
+use scope::symbol::{SymbolTable, resolve_outward};
+use Identifier;
+
+/// Visibility level of a symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Visibility {
+    /// Accessible from any scope.
+    #[default]
+    Public,
+    /// Accessible only from the scope defining the symbol, and scopes lexically within it.
+    Private,
+}
+
+/// Trait for symbols which carry a visibility level.
+pub trait Visible {
+    /// The visibility level of this symbol.
+    fn visibility(&self) -> Visibility;
+}
+
+impl<T> Scope<T> {
+    /// Returns `true` if this scope is `other`, or is lexically nested within `other`.
+    pub fn is_within(&self, other: &Rc<RefCell<Scope<T>>>) -> bool {
+        if ptr::eq(self, other.as_ptr()) {
+            return true;
+        }
+        match self.parent {
+            Some(ref parent) => Rc::ptr_eq(parent, other) || parent.borrow().is_within(other),
+            None => false,
+        }
+    }
+}
+
+/// Methods for resolving symbols while respecting symbol visibility.
+pub trait AccessStore<Sym> {
+    /// Resolves a symbol from this scope, respecting visibility of symbols found in base scopes
+    /// or through imports.
+    ///
+    /// # Failures
+    /// Fails with `ResolveError::Inaccessible` if the first symbol found for the identifier is not
+    /// accessible from this scope, or another `ResolveError` if no symbol is found.
+    fn resolve_access(&self, ident: &Identifier) -> Result<Sym, ResolveError>;
+    /// Resolves a path of identifiers (e.g. `a.b.c`) from this scope, as
+    /// `MemberStore::resolve_path`, but respecting visibility of each member.
+    ///
+    /// # Failures
+    /// Fails with `ResolveError::Inaccessible` if any member along the path is not accessible
+    /// from this scope, `ResolveError::Undefined` if any segment cannot be resolved or any
+    /// non-final segment has no member scope, or `ResolveError::EmptyPath` if `path` is empty.
+    fn resolve_path_access(&self, path: &[Identifier]) -> Result<Sym, ResolveError>
+        where Sym: Scoped<Self>, Self: Default + Sized;
+}
+
+impl<Sym, Tbl> AccessStore<Sym> for Scope<Tbl>
+    where Sym: Clone + Visible, Tbl: SymbolTable<Sym>
+{
+    fn resolve_access(&self, ident: &Identifier) -> Result<Sym, ResolveError> {
+        resolve_outward(ident, |candidate| resolve_access_from(self, self, candidate))
+    }
+    fn resolve_path_access(&self, path: &[Identifier]) -> Result<Sym, ResolveError>
+        where Sym: Scoped<Scope<Tbl>>, Scope<Tbl>: Default
+    {
+        let (first, rest) = path.split_first().ok_or(ResolveError::EmptyPath)?;
+        let mut symbol = self.resolve_access(first)?;
+        for ident in rest {
+            let members = symbol.scope().ok_or(ResolveError::Undefined(*ident))?;
+            symbol = match find_member(&members, ident) {
+                Some((member, ref owner)) if accessible(self, &member, owner) => member,
+                Some(_) => return Err(ResolveError::Inaccessible(*ident)),
+                None => return Err(ResolveError::Undefined(*ident)),
+            };
+        }
+        Ok(symbol)
+    }
+}
+
+fn accessible<Sym: Visible, Tbl>(accessor: &Scope<Tbl>, symbol: &Sym,
+    owner: &Rc<RefCell<Scope<Tbl>>>) -> bool
+{
+    symbol.visibility() == Visibility::Public || accessor.is_within(owner)
+}
+
+fn resolve_access_from<Sym, Tbl>(scope: &Scope<Tbl>, accessor: &Scope<Tbl>, ident: &Identifier)
+    -> Result<Sym, ResolveError>
+    where Sym: Clone + Visible, Tbl: SymbolTable<Sym>
+{
+    if let Some(symbol) = scope.item.symbol_get(ident) {
+        return Ok(symbol.clone());
+    }
+    for base in &scope.bases {
+        match find_member(base, ident) {
+            Some((symbol, ref owner)) if accessible(accessor, &symbol, owner) => return Ok(symbol),
+            Some(_) => return Err(ResolveError::Inaccessible(*ident)),
+            None => {}
+        }
+    }
+    let imported = scope.lookup_import(ident,
+        |symbol: &Sym, owner| accessible(accessor, symbol, owner))?;
+    if let Some(symbol) = imported {
+        return Ok(symbol);
+    }
+    match scope.parent {
+        Some(ref parent) => resolve_access_from(&parent.borrow(), accessor, ident),
+        None => Err(ResolveError::Undefined(*ident)),
+    }
+}
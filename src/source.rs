@@ -0,0 +1,284 @@
+//! Source file handling.
+
+use std::fmt;
+
+use span::Span;
+
+/// A change to source text: replacement of the text in `span` with `text`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    /// Range of the original text to replace.
+    pub span: Span,
+    /// Replacement text.
+    pub text: String,
+}
+impl TextEdit {
+    /// Creates a new edit replacing the text in `span` with `text`.
+    pub fn new<S: Into<String>>(span: Span, text: S) -> TextEdit {
+        TextEdit { span, text: text.into() }
+    }
+    /// Creates a new edit inserting `text` at `pos`.
+    pub fn insert<S: Into<String>>(pos: usize, text: S) -> TextEdit {
+        TextEdit::new(Span::new(pos, pos), text)
+    }
+    /// Creates a new edit deleting the text in `span`.
+    pub fn delete(span: Span) -> TextEdit {
+        TextEdit::new(span, "")
+    }
+}
+
+/// Range of source text changed by an edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangedRange {
+    /// The replaced range, in positions of the text before the edit.
+    pub old: Span,
+    /// The replacement range, in positions of the text after the edit.
+    pub new: Span,
+}
+impl ChangedRange {
+    /// Returns `true` if a span of the pre-edit text is affected by this change. Spans which
+    /// merely touch the changed range are considered affected, since an edit adjacent to a token
+    /// may extend it.
+    pub fn intersects(&self, span: Span) -> bool {
+        span.start <= self.old.end && self.old.start <= span.end
+    }
+    /// Maps a span of the pre-edit text to its position in the post-edit text, or `None` if the
+    /// span is affected by this change.
+    pub fn shift(&self, span: Span) -> Option<Span> {
+        if self.intersects(span) {
+            None
+        } else if span.end < self.old.start {
+            Some(span)
+        } else {
+            let start = span.start - self.old.end + self.new.end;
+            Some(Span::new(start, start + span.len()))
+        }
+    }
+}
+
+/// A source file: a name (file path or virtual name) along with its text, which can be updated
+/// by edits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    name: String,
+    text: String,
+    version: u64,
+}
+impl SourceFile {
+    /// Creates a new source file with the specified name and contents.
+    pub fn new<N: Into<String>, T: Into<String>>(name: N, text: T) -> SourceFile {
+        SourceFile {
+            name: name.into(),
+            text: text.into(),
+            version: 0,
+        }
+    }
+    /// Name of this source file.
+    pub fn name(&self) -> &str { &self.name }
+    /// Current contents of this source file.
+    pub fn text(&self) -> &str { &self.text }
+    /// Number of edits applied to this source file.
+    pub fn version(&self) -> u64 { self.version }
+
+    /// Applies an edit to this source file, returning the range of text that changed.
+    ///
+    /// # Failures
+    /// Fails if the edit's span is out of bounds or does not lie on character boundaries.
+    pub fn apply(&mut self, edit: TextEdit) -> Result<ChangedRange, String> {
+        let span = edit.span;
+        if span.start > span.end || span.end > self.text.len()
+            || !self.text.is_char_boundary(span.start) || !self.text.is_char_boundary(span.end)
+        {
+            return Err(format!("invalid edit range {} for source file {}", span, self.name));
+        }
+        self.text.replace_range(span.start..span.end, &edit.text);
+        self.version += 1;
+        Ok(ChangedRange {
+            old: span,
+            new: Span::new(span.start, span.start + edit.text.len()),
+        })
+    }
+}
+impl fmt::Display for SourceFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Identifier for a file registered in a `SourceMap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(pub usize);
+
+/// Zero-based line and column location within a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// File containing the location.
+    pub file: FileId,
+    /// Zero-based line number.
+    pub line: usize,
+    /// Zero-based column number, in the units (UTF-8 bytes or UTF-16 code units) requested.
+    pub column: usize,
+}
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        write!(f, "{}:{}", self.line + 1, self.column + 1)
+    }
+}
+
+/// Source file registered in a source map, along with its global position and line index.
+#[derive(Debug, Clone, PartialEq)]
+struct MappedFile {
+    file: SourceFile,
+    start: usize,
+    line_starts: Vec<usize>,
+}
+impl MappedFile {
+    fn new(file: SourceFile, start: usize) -> MappedFile {
+        let mut line_starts = vec![0];
+        line_starts.extend(file.text().match_indices('\n').map(|(i, _)| i + 1));
+        MappedFile { file, start, line_starts }
+    }
+    fn end(&self) -> usize { self.start + self.file.text().len() }
+    // (line, byte offset of line start) for a file-local offset
+    fn line_of(&self, local: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&local) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        (line, self.line_starts[line])
+    }
+    // text of a line, excluding the line terminator
+    fn line_text(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line)?;
+        let end = self.line_starts.get(line + 1).map_or(self.file.text().len(), |&next| next - 1);
+        Some(&self.file.text()[start..end])
+    }
+}
+
+/// Registry of source files, assigning each file a distinct range of global byte offsets and
+/// converting between global offsets and file / line / column locations.
+///
+/// Files are laid out one after another in the global offset space, with a one-byte gap after
+/// each file so that the end-of-file position of every file is distinct.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SourceMap {
+    files: Vec<MappedFile>,
+}
+impl SourceMap {
+    /// Creates a new, empty source map.
+    pub fn new() -> SourceMap { SourceMap::default() }
+
+    /// Registers a source file, returning its identifier.
+    pub fn add_file(&mut self, file: SourceFile) -> FileId {
+        let start = self.files.last().map_or(0, |last| last.end() + 1);
+        self.files.push(MappedFile::new(file, start));
+        FileId(self.files.len() - 1)
+    }
+
+    /// Registers a source file with the specified name (file path or virtual name) and contents,
+    /// returning its identifier.
+    pub fn add<N: Into<String>, T: Into<String>>(&mut self, name: N, text: T) -> FileId {
+        self.add_file(SourceFile::new(name, text))
+    }
+
+    /// Accesses a registered source file.
+    pub fn file(&self, id: FileId) -> Option<&SourceFile> {
+        self.files.get(id.0).map(|mapped| &mapped.file)
+    }
+
+    /// Finds a registered source file by name.
+    pub fn find(&self, name: &str) -> Option<FileId> {
+        self.files.iter().position(|mapped| mapped.file.name() == name).map(FileId)
+    }
+
+    /// Iterates over the identifiers of all registered files.
+    pub fn file_ids(&self) -> impl Iterator<Item = FileId> {
+        (0..self.files.len()).map(FileId)
+    }
+
+    /// Global span covered by a registered source file.
+    pub fn span(&self, id: FileId) -> Option<Span> {
+        self.files.get(id.0).map(|mapped| Span::new(mapped.start, mapped.end()))
+    }
+
+    /// Converts a file-local span to a global span.
+    pub fn global_span(&self, id: FileId, local: Span) -> Option<Span> {
+        self.files.get(id.0).map(|mapped| {
+            Span::new(mapped.start + local.start, mapped.start + local.end)
+        })
+    }
+
+    /// Finds the file containing a global offset. The position immediately following the end of a
+    /// file is considered part of the file.
+    pub fn lookup_file(&self, offset: usize) -> Option<FileId> {
+        let index = match self.files.binary_search_by_key(&offset, |mapped| mapped.start) {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(next) => next - 1,
+        };
+        if offset <= self.files[index].end() { Some(FileId(index)) } else { None }
+    }
+
+    /// Text of a (zero-based) line in a registered file, excluding the line terminator.
+    pub fn line_text(&self, id: FileId, line: usize) -> Option<&str> {
+        self.files.get(id.0).and_then(|mapped| mapped.line_text(line))
+    }
+
+    /// Converts a global offset to a file, line and column location, with the column counted in
+    /// UTF-8 bytes.
+    pub fn location(&self, offset: usize) -> Option<Location> {
+        let file = self.lookup_file(offset)?;
+        let mapped = &self.files[file.0];
+        let local = offset - mapped.start;
+        let (line, line_start) = mapped.line_of(local);
+        Some(Location { file, line, column: local - line_start })
+    }
+
+    /// Converts a global offset to a file, line and column location, with the column counted in
+    /// UTF-16 code units (as used by many editor protocols).
+    ///
+    /// # Failures
+    /// Returns `None` if the offset is not within a file or is not on a character boundary.
+    pub fn location_utf16(&self, offset: usize) -> Option<Location> {
+        let file = self.lookup_file(offset)?;
+        let mapped = &self.files[file.0];
+        let local = offset - mapped.start;
+        let (line, line_start) = mapped.line_of(local);
+        let prefix = mapped.file.text().get(line_start..local)?;
+        Some(Location { file, line, column: prefix.encode_utf16().count() })
+    }
+
+    /// Converts a file, line and column location (with column counted in UTF-8 bytes) to a global
+    /// offset.
+    pub fn offset(&self, location: Location) -> Option<usize> {
+        let mapped = self.files.get(location.file.0)?;
+        let line = mapped.line_text(location.line)?;
+        if location.column > line.len() {
+            return None;
+        }
+        Some(mapped.start + mapped.line_starts[location.line] + location.column)
+    }
+
+    /// Converts a file, line and column location (with column counted in UTF-16 code units) to a
+    /// global offset.
+    pub fn offset_utf16(&self, location: Location) -> Option<usize> {
+        let mapped = self.files.get(location.file.0)?;
+        let line = mapped.line_text(location.line)?;
+        let mut units = 0;
+        for (index, c) in line.char_indices() {
+            if units >= location.column {
+                return if units == location.column {
+                    Some(mapped.start + mapped.line_starts[location.line] + index)
+                } else {
+                    None
+                };
+            }
+            units += c.len_utf16();
+        }
+        if units == location.column {
+            Some(mapped.start + mapped.line_starts[location.line] + line.len())
+        } else {
+            None
+        }
+    }
+}
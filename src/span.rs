@@ -0,0 +1,55 @@
+//! Source span types.
+
+use std::fmt;
+
+/// Byte range (`start` inclusive, `end` exclusive) within a source string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Span {
+    /// Starting byte position.
+    pub start: usize,
+    /// Ending byte position (one past the last byte).
+    pub end: usize,
+}
+impl Span {
+    /// Creates a new span from `start` to `end`.
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+    /// Length of the span, in bytes.
+    pub fn len(&self) -> usize { self.end - self.start }
+    /// Returns `true` if the span covers no bytes.
+    pub fn is_empty(&self) -> bool { self.start == self.end }
+    /// Returns `true` if `pos` lies within this span.
+    pub fn contains(&self, pos: usize) -> bool { self.start <= pos && pos < self.end }
+}
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> ::std::result::Result<(), fmt::Error> {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// An item along with the span of source it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Spanned<T> {
+    /// The spanned item.
+    pub item: T,
+    /// Location of the item in the source.
+    pub span: Span,
+}
+impl<T> Spanned<T> {
+    /// Creates a new spanned item.
+    pub fn new(item: T, span: Span) -> Spanned<T> {
+        Spanned { item, span }
+    }
+}
+
+/// Trait for objects which may be associated with a location in the source.
+pub trait HasSpan {
+    /// The location of this object in the source (if known).
+    fn span(&self) -> Option<Span>;
+}
+impl<T> HasSpan for Spanned<T> {
+    fn span(&self) -> Option<Span> { Some(self.span) }
+}
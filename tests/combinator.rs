@@ -0,0 +1,33 @@
+extern crate sindra;
+
+use sindra::combinator::*;
+use sindra::{Span, Spanned};
+
+#[test]
+fn test_combinators() {
+    let source = &r#"4 + 2.5 * "foo""#.to_string();
+
+    let (rest, lit) = int_lit(source)(source).unwrap();
+    assert_eq!(lit, Spanned::new(4, Span::new(0, 1)));
+    assert_eq!(rest, r#" + 2.5 * "foo""#);
+
+    let (rest, lit) = float_lit(source)(&rest[3..]).unwrap();
+    assert_eq!(lit, Spanned::new(2.5, Span::new(4, 7)));
+
+    let (rest, lit) = string_lit(source)(&rest[3..]).unwrap();
+    assert_eq!(lit, Spanned::new("foo".to_string(), Span::new(10, 15)));
+    assert_eq!(rest, "");
+
+    assert!(int_lit(source)(&source[1..]).is_err());
+    assert!(int_lit(source)("4").is_err());
+}
+
+#[cfg(feature = "nom")]
+#[test]
+fn test_nom_combinators() {
+    let source = "0x10 20";
+    let (rest, lit) = nom::int_lit(source)(source).unwrap();
+    assert_eq!(lit, Spanned::new(16, Span::new(0, 4)));
+    assert_eq!(rest, " 20");
+    assert!(nom::float_lit(source)(rest).is_err());
+}
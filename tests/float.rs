@@ -15,3 +15,9 @@ fn test_float() {
     assert_eq!(float("43e2"), Ok(4300.0));
     assert_eq!(float("43e-2"), Ok(0.43));
 }
+
+#[test]
+fn test_float_ext() {
+    assert_eq!(float_ext("x * 4.3e2;", 4), Ok((9, 430.0)));
+    assert!(float_ext("x * 43;", 4).is_err());
+}
@@ -0,0 +1,72 @@
+extern crate sindra;
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use sindra::scope::{CallStack, DeclarationStore, Handle, Heap, MemoryScope, Stack, Trace};
+use sindra::{Identifier, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Val {
+    Int(i64),
+    List(Handle),
+}
+impl fmt::Display for Val {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Val::Int(i) => write!(f, "{}", i),
+            Val::List(_) => write!(f, "[...]"),
+        }
+    }
+}
+impl Value for Val {}
+impl Trace for Val {
+    fn trace(&self, handles: &mut Vec<Handle>) {
+        if let Val::List(handle) = *self {
+            handles.push(handle);
+        }
+    }
+}
+
+fn ident(name: &str) -> Identifier { Identifier::new(name) }
+
+#[test]
+fn collect_unreachable() {
+    let mut heap = Heap::<Vec<Val>>::new();
+    let inner = heap.alloc(vec![Val::Int(1), Val::Int(2)]);
+    let outer = heap.alloc(vec![Val::List(inner)]);
+    let garbage = heap.alloc(vec![Val::Int(3)]);
+    // cycle which is not reachable from any root
+    let a = heap.alloc(vec![]);
+    let b = heap.alloc(vec![Val::List(a)]);
+    heap.get_mut(a).unwrap().push(Val::List(b));
+    assert_eq!(heap.len(), 5);
+
+    let mut scope = MemoryScope::<(), Val>::default();
+    scope.declare_init(ident("x"), (), Val::List(outer));
+    let scope = Rc::new(RefCell::new(scope)).push();
+    assert_eq!(heap.collect(&[&scope]), 3);
+    assert_eq!(heap.len(), 2);
+    assert_eq!(heap.get(inner), Some(&vec![Val::Int(1), Val::Int(2)]));
+    assert!(heap.contains(outer));
+    assert!(!heap.contains(garbage) && !heap.contains(a) && !heap.contains(b));
+
+    // freed slots are reused, but stale handles stay invalid
+    let reused = heap.alloc(vec![]);
+    assert!(heap.contains(reused));
+    assert_eq!(heap.get(garbage), None);
+    assert_eq!(heap.get_mut(a), None);
+}
+
+#[test]
+fn collect_from_call_stack() {
+    let mut heap = Heap::<Vec<Val>>::new();
+    let list = heap.alloc(vec![Val::Int(1)]);
+    let mut stack = CallStack::<Val>::new();
+    stack.call(1, None, ()).unwrap().set(0, Val::List(list)).unwrap();
+    assert_eq!(heap.collect(&[&stack]), 0);
+    stack.ret().unwrap();
+    assert_eq!(heap.collect(&[&stack]), 1);
+    assert!(heap.is_empty());
+}
@@ -0,0 +1,77 @@
+extern crate sindra;
+
+use std::collections::HashSet;
+use std::mem;
+
+use sindra::{Identifier, IdentPath, PathError, PathSyntax, SyntaxContext};
+
+#[test]
+fn interning() {
+    let a = Identifier::new("alpha");
+    let b = Identifier::from("alpha".to_string());
+    let c = Identifier::from("beta");
+    assert_eq!(a, b);
+    assert_eq!(a.index(), b.index());
+    assert_ne!(a, c);
+    assert_eq!(a.as_str(), "alpha");
+    assert_eq!(c.to_string(), "beta");
+    assert_eq!(format!("{:?}", a), "Identifier(\"alpha\")");
+
+    let set: HashSet<Identifier> = vec![a, b, c].into_iter().collect();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn paths() {
+    let path = IdentPath::parse("std::io::Write").unwrap();
+    assert_eq!(path.len(), 3);
+    assert_eq!(path[1], Identifier::new("io"));
+    assert_eq!(path.to_string(), "std::io::Write");
+    assert_eq!(path.parent().unwrap().to_string(), "std::io");
+    assert!(path.is_qualified() && !IdentPath::from(Identifier::new("x")).is_qualified());
+
+    let syntax = PathSyntax::new(&[".", "::"]);
+    let mixed = syntax.parse("a::b.c").unwrap();
+    assert_eq!(syntax.format(&mixed), "a.b.c");
+    assert_eq!(syntax.parse(&syntax.format(&mixed)), Ok(mixed.clone()));
+    assert_eq!("a::b::c".parse::<IdentPath>(), Ok(mixed));
+    // a single colon is not a separator
+    assert_eq!(IdentPath::parse("a:b").unwrap().len(), 1);
+
+    assert_eq!(IdentPath::parse(""), Err(PathError::Empty));
+    assert_eq!(IdentPath::parse("a::::b"), Err(PathError::EmptySegment(3)));
+    assert_eq!(syntax.parse(".a"), Err(PathError::EmptySegment(0)));
+    assert_eq!(syntax.parse("a."), Err(PathError::EmptySegment(2)));
+}
+
+#[test]
+fn hygiene() {
+    let user = Identifier::new("tmp");
+    assert!(user.context().is_root());
+    let expansion = SyntaxContext::fresh();
+    let marked = user.with_context(expansion);
+    assert_ne!(user, marked);
+    assert_eq!(marked.as_str(), "tmp");
+    assert_eq!(marked.index(), user.index());
+    assert_eq!(marked, Identifier::new("tmp").with_context(expansion));
+    assert_eq!(marked.with_context(SyntaxContext::ROOT), user);
+    assert_ne!(SyntaxContext::fresh(), expansion);
+    assert_eq!(format!("{:?}", marked), format!("Identifier(\"tmp\", {})", expansion.index()));
+
+    // marks are stripped outward, ending at the root context
+    let nested = expansion.nested();
+    assert_eq!(nested.parent(), Some(expansion));
+    assert_eq!(expansion.parent(), Some(SyntaxContext::ROOT));
+    assert_eq!(SyntaxContext::ROOT.parent(), None);
+    assert_eq!(user.with_context(nested).outward().collect::<Vec<_>>(), vec![
+        user.with_context(nested), marked, user]);
+    assert_eq!(user.strip_mark(), None);
+
+    // identifiers remain the size of a `u32`, whatever their context
+    assert_eq!(mem::size_of::<Identifier>(), 4);
+
+    let a = Identifier::gensym("tmp");
+    let b = Identifier::gensym("tmp");
+    assert!(a != b && a != user);
+    assert_eq!(a.to_string(), "tmp");
+}
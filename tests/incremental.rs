@@ -0,0 +1,62 @@
+#[macro_use] extern crate sindra;
+
+use sindra::incremental::{ReuseCache, ShiftSpans};
+use sindra::node::Node;
+use sindra::source::{ChangedRange, SourceFile, TextEdit};
+use sindra::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Num(i64),
+}
+annotate!(Item);
+impl ShiftSpans for Item {
+    fn shift_spans(&mut self, _: &ChangedRange) {}
+}
+
+#[test]
+fn test_source_edit() {
+    let mut file = SourceFile::new("test.lang", "let x = 1;");
+    let change = file.apply(TextEdit::new(Span::new(8, 9), "42")).unwrap();
+    assert_eq!(file.text(), "let x = 42;");
+    assert_eq!(file.version(), 1);
+    assert_eq!(change, ChangedRange { old: Span::new(8, 9), new: Span::new(8, 10) });
+
+    assert_eq!(change.shift(Span::new(0, 3)), Some(Span::new(0, 3)));
+    assert_eq!(change.shift(Span::new(9, 10)), None);
+    assert_eq!(change.shift(Span::new(6, 7)), Some(Span::new(6, 7)));
+
+    let change = file.apply(TextEdit::insert(0, "  ")).unwrap();
+    assert_eq!(change.shift(Span::new(4, 5)), Some(Span::new(6, 7)));
+
+    assert!(file.apply(TextEdit::delete(Span::new(10, 20))).is_err());
+}
+
+#[test]
+fn test_reuse() {
+    // "1 2 3" -> "1 22 3"
+    let mut file = SourceFile::new("test.lang", "1 2 3");
+    let old = vec![
+        Node::with_span(Item::Num(1), Span::new(0, 1)),
+        Node::with_span(Item::Num(2), Span::new(2, 3)),
+        Node::with_span(Item::Num(3), Span::new(4, 5)),
+    ];
+    let change = file.apply(TextEdit::insert(3, "2")).unwrap();
+    let mut cache = ReuseCache::new(change);
+    let offered = old.into_iter().map(|node| cache.offer(node)).collect::<Vec<_>>();
+    assert_eq!(offered, vec![true, false, true]);
+    assert_eq!(cache.len(), 2);
+
+    assert_eq!(cache.peek(5), Some(Span::new(5, 6)));
+    let reused = cache.take(5).unwrap();
+    assert_eq!(reused.item, Item::Num(3));
+    assert!(cache.take(2).is_none());
+    assert!(cache.take(0).is_some());
+    assert!(cache.is_empty());
+
+    // a smaller subtree at the position of a stored subtree is rejected
+    assert!(cache.offer(Node::with_span(Item::Num(12), Span::new(0, 2))));
+    assert!(!cache.offer(Node::with_span(Item::Num(1), Span::new(0, 1))));
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.peek(0), Some(Span::new(0, 2)));
+}
@@ -7,4 +7,17 @@ fn test_num() {
     assert_eq!(int("0"), Ok(0));
 
     assert_eq!(int("4"), Ok(4));
+    assert_eq!(int("1_000"), Ok(1000));
+    assert_eq!(int("0x1AF3"), Ok(0x1AF3));
+    assert_eq!(int("0o754"), Ok(0o754));
+    assert_eq!(int("0b0101"), Ok(0b0101));
+
+    assert!(int("4a").is_err());
+}
+
+#[test]
+fn test_num_ext() {
+    assert_eq!(int_ext("x + 0x10)", 4), Ok((8, 16)));
+    assert_eq!(int_ext("42", 0), Ok((2, 42)));
+    assert!(int_ext("x + y", 4).is_err());
 }
@@ -0,0 +1,294 @@
+extern crate sindra;
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use sindra::scope::{CallError, CallStack, DeclarationStore, MemoryError, MemoryScope, MemoryStore,
+    MemorySnapshot, Mutability, PersistentScope, SharedMemoryStore, Stack, SymbolScope,
+    SymbolStore, TypedMemoryStore};
+use sindra::{FindType, Identifier, SyntaxContext, Type, Typed, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Ty { Int, Str }
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", self.name()) }
+}
+impl Type for Ty {
+    fn name(&self) -> &str {
+        match *self { Ty::Int => "int", Ty::Str => "string" }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Val { Int(i64), Str(String) }
+impl fmt::Display for Val {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self { Val::Int(i) => write!(f, "{}", i), Val::Str(ref s) => write!(f, "{}", s) }
+    }
+}
+impl Value for Val {}
+impl FindType for Val {
+    type Output = Ty;
+    fn ty(&self) -> Ty {
+        match *self { Val::Int(_) => Ty::Int, Val::Str(_) => Ty::Str }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Variable(Option<Ty>);
+impl Typed<Ty> for Variable {
+    fn ty(&self) -> Option<Ty> { self.0.clone() }
+    fn set_type(&mut self, ty: Option<Ty>) { self.0 = ty; }
+    fn promoted(&self) -> Option<Ty> { self.0.clone() }
+}
+
+fn ident(name: &str) -> Identifier { Identifier::new(name) }
+
+#[test]
+fn declare_and_assign() {
+    let mut scope = MemoryScope::<Variable, Val>::default();
+    assert_eq!(scope.declare(ident("x"), Variable(None)), None);
+    assert_eq!(scope.read(&ident("x")), Err(MemoryError::Uninitialised(ident("x"))));
+    assert_eq!(scope.read(&ident("y")), Err(MemoryError::Undeclared(ident("y"))));
+
+    assert_eq!(scope.assign(ident("x"), Val::Int(1)), Ok(None));
+    assert_eq!(scope.assign(ident("x"), Val::Int(2)), Ok(Some(Val::Int(1))));
+    assert_eq!(scope.read(&ident("x")), Ok(Val::Int(2)));
+    assert_eq!(scope.assign(ident("y"), Val::Int(3)), Err(MemoryError::Undeclared(ident("y"))));
+
+    scope.declare_init(ident("z"), Variable(None), Val::Str("a".to_string()));
+    assert_eq!(scope.read(&ident("z")), Ok(Val::Str("a".to_string())));
+    assert_eq!(MemoryError::Undeclared(ident("y")).to_string(), "undeclared identifier: y");
+}
+
+#[test]
+fn assign_typed() {
+    let mut scope = MemoryScope::<Variable, Val>::default();
+    scope.declare(ident("x"), Variable(Some(Ty::Int)));
+    scope.declare(ident("any"), Variable(None));
+    assert_eq!(scope.assign_typed(ident("x"), Val::Int(1)), Ok(None));
+    assert_eq!(scope.assign_typed(ident("x"), Val::Str("a".to_string())),
+        Err(MemoryError::TypeMismatch {
+            ident: ident("x"),
+            expected: "int".to_string(),
+            found: "string".to_string(),
+        }));
+    assert_eq!(scope.assign_typed(ident("any"), Val::Str("a".to_string())), Ok(None));
+    assert_eq!(scope.read(&ident("x")), Ok(Val::Int(1)));
+}
+
+#[test]
+fn mutability() {
+    let mut scope = MemoryScope::<Variable, Val>::default();
+    scope.declare_with(ident("a"), Variable(None), Mutability::Immutable, None).unwrap();
+    scope.declare_with(ident("b"), Variable(None),
+        Mutability::Immutable, Some(Val::Int(1))).unwrap();
+    scope.declare_with(ident("c"), Variable(None), Mutability::Const, Some(Val::Int(2))).unwrap();
+
+    // deferred initialisation of an immutable binding
+    assert_eq!(scope.set(ident("a"), Val::Int(3)), Ok(None));
+    assert_eq!(scope.set(ident("a"), Val::Int(4)), Err(MemoryError::Immutable(ident("a"))));
+    assert_eq!(scope.set(ident("b"), Val::Int(5)), Err(MemoryError::Immutable(ident("b"))));
+    assert_eq!(scope.set(ident("c"), Val::Int(6)), Err(MemoryError::Const(ident("c"))));
+    assert_eq!(scope.read(&ident("a")), Ok(Val::Int(3)));
+    assert_eq!(scope.read(&ident("c")), Ok(Val::Int(2)));
+    // constants must be initialised when declared
+    assert_eq!(scope.declare_with(ident("d"), Variable(None), Mutability::Const, None),
+        Err(MemoryError::UninitialisedConst(ident("d"))));
+    assert_eq!(scope.read(&ident("d")), Err(MemoryError::Undeclared(ident("d"))));
+
+    let mut inner = Rc::new(RefCell::new(scope)).push();
+    assert_eq!(inner.borrow_mut().set(ident("b"), Val::Int(7)),
+        Err(MemoryError::Immutable(ident("b"))));
+    // shadowing an immutable binding with a mutable one
+    inner.borrow_mut().declare_init(ident("b"), Variable(None), Val::Int(8));
+    assert_eq!(inner.borrow_mut().set(ident("b"), Val::Int(9)), Ok(Some(Val::Int(8))));
+    inner.pop().unwrap();
+    assert_eq!(inner.borrow().read(&ident("b")), Ok(Val::Int(1)));
+}
+
+#[test]
+fn persistent_declare() {
+    let mut scope = PersistentScope::<Variable, Val>::new();
+    scope.declare_init(ident("x"), Variable(None), Val::Int(1));
+    let mut inner = scope.push();
+    inner.declare(ident("y"), Variable(None));
+    assert_eq!(inner.read(&ident("y")), Err(MemoryError::Uninitialised(ident("y"))));
+    assert_eq!(inner.assign(ident("x"), Val::Int(2)), Ok(Some(Val::Int(1))));
+    assert_eq!(inner.read(&ident("x")), Ok(Val::Int(2)));
+    assert_eq!(scope.read(&ident("x")), Ok(Val::Int(1)));
+    assert_eq!(inner.read(&ident("z")), Err(MemoryError::Undeclared(ident("z"))));
+
+    inner.declare_with(ident("k"), Variable(None), Mutability::Const, Some(Val::Int(3))).unwrap();
+    assert_eq!(inner.assign(ident("k"), Val::Int(4)), Err(MemoryError::Const(ident("k"))));
+    assert_eq!(inner.declare_with(ident("k"), Variable(None), Mutability::Const, None),
+        Err(MemoryError::UninitialisedConst(ident("k"))));
+    assert_eq!(inner.read(&ident("k")), Ok(Val::Int(3)));
+}
+
+fn factorial(stack: &mut CallStack<Val, &'static str>, n: i64) -> Result<i64, CallError> {
+    stack.call(1, None, "factorial")?.set(0, Val::Int(n))?;
+    let n = match *stack.get(0)? { Val::Int(n) => n, Val::Str(_) => unreachable!() };
+    let result = if n <= 1 { 1 } else { n * factorial(stack, n - 1)? };
+    stack.ret()?;
+    Ok(result)
+}
+
+#[test]
+fn call_stack() {
+    let mut stack = CallStack::<Val, &'static str>::with_max_depth(8);
+    assert_eq!(factorial(&mut stack, 5), Ok(120));
+    assert_eq!(stack.depth(), 0);
+    assert_eq!(factorial(&mut stack, 10), Err(CallError::StackOverflow { max_depth: 8 }));
+    assert_eq!(stack.depth(), 8);
+    assert!(stack.frames().all(|frame| frame.meta == "factorial"));
+    assert_eq!(stack.frame(0).unwrap().get(0), Ok(&Val::Int(3)));
+    assert_eq!(stack.frame(7).unwrap().get(0), Ok(&Val::Int(10)));
+    assert!(stack.frame(8).is_none());
+
+    let mut stack = CallStack::<Val>::new();
+    assert_eq!(stack.ret(), Err(CallError::EmptyStack));
+    stack.call(2, Some(42), ()).unwrap();
+    assert_eq!(stack.get(1), Err(CallError::Uninitialised(1)));
+    assert_eq!(stack.set(2, Val::Int(0)), Err(CallError::InvalidSlot(2)));
+    assert_eq!(stack.ret().unwrap().return_address, Some(42));
+}
+
+#[test]
+fn block_slots() {
+    // fn f(a, b) { { let c; } }
+    let func = Rc::new(RefCell::new(SymbolScope::<&str>::default()));
+    func.borrow_mut().define(ident("a"), "a");
+    func.borrow_mut().define(ident("b"), "b");
+    let block = func.push();
+    block.borrow_mut().define(ident("c"), "c");
+
+    // block slots restart at 0, overlapping the function's own slots
+    let (_, a) = block.borrow().resolve_with_location(&ident("a")).unwrap();
+    let (_, c) = block.borrow().resolve_with_location(&ident("c")).unwrap();
+    assert_eq!((a.depth, a.slot), (1, 0));
+    assert_eq!((c.depth, c.slot), (0, 0));
+
+    // so the block's slots are offset past those already allocated in the frame
+    let offset = func.borrow().item.len();
+    let mut stack = CallStack::<Val>::new();
+    stack.call(offset + block.borrow().item.len(), None, ()).unwrap();
+    stack.set(a.slot, Val::Int(1)).unwrap();
+    stack.set(offset + c.slot, Val::Int(2)).unwrap();
+    assert_eq!(stack.get(a.slot), Ok(&Val::Int(1)));
+    assert_eq!(stack.get(offset + c.slot), Ok(&Val::Int(2)));
+}
+
+#[test]
+fn borrowed_access() {
+    let mut global = MemoryScope::<Variable, Val>::default();
+    global.declare_init(ident("s"), Variable(None), Val::Str("abc".to_string()));
+    global.declare_with(ident("k"), Variable(None), Mutability::Const, Some(Val::Int(1))).unwrap();
+    global.declare(ident("u"), Variable(None));
+    let global = Rc::new(RefCell::new(global));
+    let inner = global.push().push();
+    inner.borrow_mut().declare_init(ident("i"), Variable(None), Val::Int(2));
+
+    let len = inner.borrow().with_value(&ident("s"), |v| match *v {
+        Val::Str(ref s) => s.len(),
+        Val::Int(_) => 0,
+    });
+    assert_eq!(len, Ok(3));
+    assert_eq!(inner.borrow().with_value(&ident("u"), |_| ()),
+        Err(MemoryError::Uninitialised(ident("u"))));
+
+    inner.borrow_mut().update(&ident("s"), |v| if let Val::Str(ref mut s) = *v { s.push('d') })
+        .unwrap();
+    assert_eq!(inner.borrow_mut().update(&ident("k"), |_| ()), Err(MemoryError::Const(ident("k"))));
+    assert_eq!(inner.borrow_mut().update(&ident("u"), |_| ()),
+        Err(MemoryError::Uninitialised(ident("u"))));
+
+    assert_eq!(inner.borrow().with_value(&ident("s"), |s| s == &Val::Str("abcd".to_string())),
+        Ok(true));
+    assert_eq!(inner.borrow().with_value(&ident("x"), |_| ()),
+        Err(MemoryError::Undeclared(ident("x"))));
+
+    // guards borrow values through the parent chain, and keep the holding scope alive
+    let guard = inner.get_ref(&ident("s")).unwrap();
+    assert!(Rc::ptr_eq(guard.scope(), &global));
+    assert_eq!(*guard.borrow().unwrap(), Val::Str("abcd".to_string()));
+    {
+        let value = guard.borrow().unwrap();
+        assert_eq!(inner.borrow().read(&ident("i")), Ok(Val::Int(2)));
+        assert_eq!(*value, Val::Str("abcd".to_string()));
+    }
+    let outer = Rc::new(RefCell::new(MemoryScope::<Variable, Val>::default()));
+    outer.borrow_mut().declare_init(ident("v"), Variable(None), Val::Int(5));
+    let chain = outer.push().push();
+    drop(outer);
+    let detached = chain.get_ref(&ident("v")).unwrap();
+    drop(chain);
+    assert_eq!(*detached.borrow().unwrap(), Val::Int(5));
+    detached.scope().borrow_mut().declare(ident("v"), Variable(None));
+    assert_eq!(detached.borrow().err(), Some(MemoryError::Uninitialised(ident("v"))));
+    assert_eq!(inner.get_ref(&ident("u")).err(), Some(MemoryError::Uninitialised(ident("u"))));
+    assert_eq!(inner.get_ref(&ident("x")).err(), Some(MemoryError::Undeclared(ident("x"))));
+
+    let mut persistent = PersistentScope::<Variable, Val>::new();
+    persistent.declare_init(ident("s"), Variable(None), Val::Str("a".to_string()));
+    let mut inner = persistent.push();
+    inner.update(&ident("s"), |v| *v = Val::Str("b".to_string())).unwrap();
+    assert_eq!(inner.get_ref(&ident("s")), Ok(&Val::Str("b".to_string())));
+    assert_eq!(persistent.get_ref(&ident("s")), Ok(&Val::Str("a".to_string())));
+}
+
+#[test]
+fn hygienic_access() {
+    let expansion = SyntaxContext::fresh();
+    let marked = |name| ident(name).with_context(expansion);
+
+    let global = Rc::new(RefCell::new(MemoryScope::<Variable, Val>::default()));
+    global.borrow_mut().declare_init(ident("count"), Variable(None), Val::Int(1));
+    global.borrow_mut().declare_init(ident("tmp"), Variable(None), Val::Int(2));
+    let block = global.push();
+    block.borrow_mut().declare_init(marked("tmp"), Variable(None), Val::Int(3));
+    assert_eq!(block.borrow_mut().assign(marked("count"), Val::Int(4)), Ok(Some(Val::Int(1))));
+    assert_eq!(global.borrow().read(&ident("count")), Ok(Val::Int(4)));
+    assert_eq!(block.borrow().read(&marked("tmp")), Ok(Val::Int(3)));
+    assert_eq!(block.borrow().read(&ident("tmp")), Ok(Val::Int(2)));
+    assert_eq!(block.borrow().read(&marked("x")), Err(MemoryError::Undeclared(marked("x"))));
+
+    let mut persistent = PersistentScope::<Variable, Val>::new();
+    persistent.declare_init(ident("count"), Variable(None), Val::Int(1));
+    let mut inner = persistent.push();
+    inner.update(&marked("count"), |v| *v = Val::Int(2)).unwrap();
+    assert_eq!(inner.read(&ident("count")), Ok(Val::Int(2)));
+    assert_eq!(inner.resolve(&marked("count")), Some(Variable(None)));
+    assert_eq!(persistent.read(&ident("count")), Ok(Val::Int(1)));
+}
+
+#[test]
+fn snapshot_diff() {
+    let mut global = MemoryScope::<&'static str, i64>::default();
+    global.declare_init(ident("x"), "int", 1);
+    global.declare_init(ident("y"), "int", 2);
+    let global = Rc::new(RefCell::new(global));
+    let inner = global.push();
+    inner.borrow_mut().declare(ident("z"), "int");
+
+    let before = MemorySnapshot::new(&inner.borrow());
+    assert_eq!(before.levels.len(), 2);
+    assert_eq!(before.to_string(), "[0] x: int {1}\n[0] y: int {2}\n[1] z: int\n");
+
+    inner.borrow_mut().set(ident("x"), 10).unwrap();
+    inner.borrow_mut().set(ident("z"), 3).unwrap();
+    let after = MemorySnapshot::new(&global.borrow());
+    let diff = before.diff(&after);
+    assert_eq!(diff.len(), 2);
+    assert_eq!(diff.to_string(), "~ [0] x: int {1} -> int {10}\n- [1] z: int\n");
+
+    let diff = after.diff(&MemorySnapshot::new(&inner.borrow()));
+    assert_eq!(diff.to_string(), "+ [1] z: int {3}\n");
+    assert!(after.diff(&after).is_empty());
+
+    // a change in mutability alone is still shown
+    global.borrow_mut().declare_with(ident("y"), "int", Mutability::Const, Some(2)).unwrap();
+    let diff = after.diff(&MemorySnapshot::new(&global.borrow()));
+    assert_eq!(diff.to_string(), "~ [0] y: int {2} (mutable) -> int {2} (const)\n");
+}
@@ -0,0 +1,80 @@
+#[macro_use] extern crate sindra;
+
+use sindra::int::int_ext;
+use sindra::log::{LogListener, LogPriority};
+use sindra::node::Node;
+use sindra::recovery::*;
+use sindra::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Int(i64),
+    Error(ErrorNode),
+}
+annotate!(Expr);
+impl Recover for Expr {
+    fn from_error(error: ParseError) -> Expr { Expr::Error(ErrorNode::from_error(error)) }
+}
+
+#[test]
+fn test_skip() {
+    let input = "let x = @@; let y = 3;";
+    assert_eq!(skip_until(input, 8, &[";", "}"]), 10);
+    assert_eq!(skip_past(input, 8, &[";", "}"]), 11);
+    assert_eq!(skip_until(input, 21, &["}"]), input.len());
+}
+
+#[test]
+fn test_recover() {
+    let input = "1; x; 3; y";
+    let mut errors = ParseErrors::new();
+    let mut items: Vec<Node<Expr>> = vec![];
+    let mut pos = 0;
+    while pos < input.len() {
+        let (end, item) = errors.recover(input, pos, &[";"], |input, pos| {
+            int_ext(input, pos).map(|(end, value)| (end, Node::new(Expr::Int(value))))
+        });
+        items.push(item);
+        pos = skip_past(input, end, &[";"]);
+        pos = input[pos..].find(|c: char| !c.is_whitespace()).map_or(input.len(), |o| pos + o);
+    }
+    assert_eq!(items.len(), 4);
+    assert_eq!(items[0].item, Expr::Int(1));
+    assert_eq!(items[2].item, Expr::Int(3));
+    match items[1].item {
+        Expr::Error(ref node) => assert_eq!(node.error.span, Span::new(3, 4)),
+        _ => panic!("expected error node"),
+    }
+    assert_eq!(errors.len(), 2);
+
+    let mut listener = LogListener::<ParseErrorMsg, _, _>::new(vec![], vec![]);
+    errors.log_to(&mut listener);
+    assert!(errors.is_empty());
+    assert_eq!(listener.flush(), Some(LogPriority::Error));
+}
+
+#[test]
+fn test_recover_at_sync() {
+    let input = ";x";
+    let mut errors = ParseErrors::new();
+    let (end, item): (usize, ErrorNode) = errors.recover(input, 0, &[";"], |_, _| {
+        Err("expected expression")
+    });
+    // the synchronisation token at the starting position is consumed
+    assert_eq!(end, 1);
+    assert_eq!(item.error.span, Span::new(0, 1));
+
+    let (end, _): (usize, ErrorNode) = errors.recover(input, 1, &[""], |_, _| {
+        Err("expected expression")
+    });
+    assert_eq!(end, 2);
+    assert_eq!(errors.len(), 2);
+}
+
+struct ParseErrorMsg(ParseError);
+impl From<ParseError> for ParseErrorMsg {
+    fn from(error: ParseError) -> ParseErrorMsg { ParseErrorMsg(error) }
+}
+impl ::std::fmt::Display for ParseErrorMsg {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result { write!(f, "{}", self.0) }
+}
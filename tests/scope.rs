@@ -0,0 +1,493 @@
+extern crate sindra;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use sindra::scope::{Access, AccessStore, CaptureStore, ConflictKind, ConflictPolicy, DefinePolicy,
+    DefineStore, Import, MemberStore, MemoryScope, MemoryStore, PersistentScope, ResolveError,
+    Scoped, ScopeGuard, ScopeKind, ScopeTree, Stack, SymbolLocation, SymbolScope, SymbolStore,
+    SymbolTable, Visibility, Visible};
+use sindra::{Identifier, IdentPath, PathSyntax, Span, Spanned, SyntaxContext};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Symbol {
+    Variable(String, Option<Rc<RefCell<SymbolScope<Symbol>>>>),
+    Class(Rc<RefCell<SymbolScope<Symbol>>>),
+    Namespace(Rc<RefCell<SymbolScope<Symbol>>>),
+    Field(&'static str),
+    PrivateField(&'static str),
+}
+impl Visible for Symbol {
+    fn visibility(&self) -> Visibility {
+        match *self {
+            Symbol::PrivateField(_) => Visibility::Private,
+            _ => Visibility::Public,
+        }
+    }
+}
+impl Scoped<SymbolScope<Symbol>> for Symbol {
+    fn scope(&self) -> Option<Rc<RefCell<SymbolScope<Symbol>>>> {
+        match *self {
+            Symbol::Variable(_, ref members) => members.clone(),
+            Symbol::Class(ref members) | Symbol::Namespace(ref members) => {
+                Some(Rc::clone(members))
+            },
+            Symbol::Field(_) | Symbol::PrivateField(_) => None,
+        }
+    }
+    fn set_scope(&mut self, scope: Option<Rc<RefCell<SymbolScope<Symbol>>>>) {
+        if let Symbol::Variable(_, ref mut members) = *self { *members = scope; }
+    }
+}
+
+fn ident(name: &str) -> Identifier { Identifier::new(name) }
+fn path(segments: &[&str]) -> Vec<Identifier> { segments.iter().map(|s| ident(s)).collect() }
+
+#[test]
+fn test_member_scopes() {
+    let global = Rc::new(RefCell::new(SymbolScope::<Symbol>::default()));
+    global.borrow_mut().define(ident("x"), Symbol::Field("global x"));
+
+    let base = global.push();
+    base.borrow_mut().define(ident("x"), Symbol::Field("base x"));
+    base.borrow_mut().define(ident("y"), Symbol::Field("base y"));
+    global.borrow_mut().define(ident("Base"), Symbol::Class(Rc::clone(&base)));
+
+    let derived = global.push();
+    derived.borrow_mut().add_base(Rc::clone(&base));
+    derived.borrow_mut().define(ident("y"), Symbol::Field("derived y"));
+    global.borrow_mut().define(ident("Derived"), Symbol::Class(Rc::clone(&derived)));
+
+    // base class scope takes precedence over lexical parent
+    assert_eq!(derived.borrow().resolve(&ident("x")), Some(Symbol::Field("base x")));
+    assert_eq!(derived.borrow().resolve(&ident("y")), Some(Symbol::Field("derived y")));
+    assert_eq!(derived.borrow().resolve_member(&ident("Base")), None);
+    assert!(derived.borrow().resolve(&ident("Base")).is_some());
+
+    let inner = derived.push();
+    inner.borrow_mut().define(ident("obj"),
+        Symbol::Variable("Derived".to_string(), Some(Rc::clone(&derived))));
+    assert_eq!(inner.borrow().resolve_path(&path(&["obj", "x"])), Some(Symbol::Field("base x")));
+    assert_eq!(inner.borrow().resolve_path(&path(&["Derived", "y"])),
+        Some(Symbol::Field("derived y")));
+    assert_eq!(inner.borrow().resolve_path(&path(&["obj", "x", "z"])), None);
+    assert_eq!(inner.borrow().resolve_path(&path(&["obj", "z"])), None);
+    assert_eq!(inner.borrow().resolve_path(&[]), None);
+
+    // member access paths
+    let dotted = PathSyntax::new(&["."]);
+    assert_eq!(inner.borrow().resolve_qualified(&dotted.parse("obj.x").unwrap()),
+        Some(Symbol::Field("base x")));
+    assert_eq!(inner.borrow().resolve_qualified(&dotted.parse("Derived.y").unwrap()),
+        Some(Symbol::Field("derived y")));
+    assert_eq!(inner.borrow().resolve_qualified(&dotted.parse("obj.z").unwrap()), None);
+}
+
+#[test]
+fn test_namespaces() {
+    let global = Rc::new(RefCell::new(SymbolScope::<Symbol>::default()));
+    let std = global.push();
+    global.borrow_mut().define(ident("std"), Symbol::Namespace(Rc::clone(&std)));
+    let io = std.push();
+    std.borrow_mut().define(ident("io"), Symbol::Namespace(Rc::clone(&io)));
+    io.borrow_mut().define(ident("Write"), Symbol::Field("io::Write"));
+    io.borrow_mut().define(ident("stdin"), Symbol::Field("io::stdin"));
+    let fmt = std.push();
+    std.borrow_mut().define(ident("fmt"), Symbol::Namespace(Rc::clone(&fmt)));
+    fmt.borrow_mut().define(ident("Write"), Symbol::Field("fmt::Write"));
+
+    // qualified lookups
+    assert_eq!(global.borrow().resolve_path(&path(&["std", "io", "Write"])),
+        Some(Symbol::Field("io::Write")));
+    assert_eq!(global.borrow().resolve_path(&path(&["std", "fmt", "Write"])),
+        Some(Symbol::Field("fmt::Write")));
+    let qualified = IdentPath::parse("std::io::stdin").unwrap();
+    assert_eq!(global.borrow().resolve_qualified(&qualified), Some(Symbol::Field("io::stdin")));
+    match global.borrow().resolve_qualified(&qualified.parent().unwrap()) {
+        Some(Symbol::Namespace(ref scope)) => assert!(Rc::ptr_eq(scope, &io)),
+        _ => panic!("expected namespace"),
+    }
+    assert_eq!(global.borrow().resolve_qualified(&IdentPath::parse("std::io::missing").unwrap()),
+        None);
+    assert_eq!(global.borrow().resolve_qualified(&IdentPath::parse("std::fmt::Write::x").unwrap()),
+        None);
+
+    let module = global.push();
+    let block = module.push();
+
+    // glob imports
+    module.borrow_mut().import(Import::glob(Rc::clone(&io))).unwrap();
+    assert_eq!(block.borrow().resolve(&ident("stdin")), Some(Symbol::Field("io::stdin")));
+    module.borrow_mut().import(Import::glob(Rc::clone(&fmt))).unwrap();
+    assert_eq!(block.borrow().try_resolve(&ident("Write")),
+        Err(ResolveError::Ambiguous(ident("Write"))));
+    assert_eq!(block.borrow().resolve(&ident("Write")), None);
+
+    // name imports take precedence over glob imports
+    module.borrow_mut().import(Import::name(Rc::clone(&fmt), ident("Write"))).unwrap();
+    assert_eq!(block.borrow().resolve(&ident("Write")), Some(Symbol::Field("fmt::Write")));
+    assert_eq!(module.borrow_mut().import(Import::name(Rc::clone(&io), ident("Write"))),
+        Err(ResolveError::Ambiguous(ident("Write"))));
+
+    // aliasing and namespace imports
+    module.borrow_mut().import(Import::alias(Rc::clone(&io), ident("Write"), ident("IoWrite")))
+        .unwrap();
+    assert_eq!(block.borrow().resolve(&ident("IoWrite")), Some(Symbol::Field("io::Write")));
+    module.borrow_mut().import(Import::name(Rc::clone(&std), ident("io"))).unwrap();
+    assert_eq!(block.borrow().resolve_path(&path(&["io", "Write"])),
+        Some(Symbol::Field("io::Write")));
+
+    // local definitions shadow imports
+    block.borrow_mut().define(ident("Write"), Symbol::Field("local"));
+    assert_eq!(block.borrow().resolve(&ident("Write")), Some(Symbol::Field("local")));
+    assert_eq!(global.borrow().try_resolve(&ident("Write")),
+        Err(ResolveError::Undefined(ident("Write"))));
+}
+
+#[test]
+fn test_visibility() {
+    let global = Rc::new(RefCell::new(SymbolScope::<Symbol>::default()));
+    let class = global.push();
+    class.borrow_mut().define(ident("secret"), Symbol::PrivateField("secret"));
+    class.borrow_mut().define(ident("open"), Symbol::Field("open"));
+    global.borrow_mut().define(ident("Class"), Symbol::Class(Rc::clone(&class)));
+    let derived = global.push();
+    derived.borrow_mut().add_base(Rc::clone(&class));
+
+    let outside = global.push();
+    assert_eq!(outside.borrow().resolve_path_access(&path(&["Class", "open"])),
+        Ok(Symbol::Field("open")));
+    assert_eq!(outside.borrow().resolve_path_access(&path(&["Class", "secret"])),
+        Err(ResolveError::Inaccessible(ident("secret"))));
+    assert_eq!(outside.borrow().resolve_path_access(&path(&["Class", "missing"])),
+        Err(ResolveError::Undefined(ident("missing"))));
+    assert_eq!(outside.borrow().resolve_path_access(&[]), Err(ResolveError::EmptyPath));
+    // visibility-unaware lookups still find the symbol
+    assert_eq!(outside.borrow().resolve_path(&path(&["Class", "secret"])),
+        Some(Symbol::PrivateField("secret")));
+
+    // within the defining scope (or nested within it), private members are accessible
+    let method = class.push();
+    assert_eq!(method.borrow().resolve_path_access(&path(&["Class", "secret"])),
+        Ok(Symbol::PrivateField("secret")));
+    assert_eq!(method.borrow().resolve_access(&ident("secret")),
+        Ok(Symbol::PrivateField("secret")));
+
+    // private members of base scopes are not accessible from derived scopes
+    assert_eq!(derived.borrow().resolve_access(&ident("secret")),
+        Err(ResolveError::Inaccessible(ident("secret"))));
+    assert_eq!(derived.borrow().resolve_access(&ident("open")), Ok(Symbol::Field("open")));
+
+    // glob imports skip private names; name imports report them
+    outside.borrow_mut().import(Import::glob(Rc::clone(&class))).unwrap();
+    assert_eq!(outside.borrow().resolve_access(&ident("secret")),
+        Err(ResolveError::Undefined(ident("secret"))));
+    outside.borrow_mut().import(Import::name(Rc::clone(&class), ident("secret"))).unwrap();
+    assert_eq!(outside.borrow().resolve_access(&ident("secret")),
+        Err(ResolveError::Inaccessible(ident("secret"))));
+}
+
+#[test]
+fn test_define_policy() {
+    let global = Rc::new(RefCell::new(SymbolScope::<Spanned<&str>>::default()));
+    global.borrow_mut().define_policy = DefinePolicy {
+        redefinition: ConflictPolicy::Error,
+        shadowing: ConflictPolicy::Warn,
+    };
+    let x = |name, start| Spanned::new(name, Span::new(start, start + 1));
+
+    assert_eq!(global.borrow_mut().define_checked(ident("x"), x("outer", 0)), Ok(None));
+    let conflict = global.borrow_mut().define_checked(ident("x"), x("again", 10)).unwrap_err();
+    assert_eq!(conflict.kind, ConflictKind::Redefinition);
+    assert_eq!(conflict.previous_span, Some(Span::new(0, 1)));
+    assert_eq!(global.borrow().resolve(&ident("x")), Some(x("outer", 0)));
+
+    // pushed scopes inherit the policy
+    let inner = global.push();
+    let conflict = inner.borrow_mut().define_checked(ident("x"), x("inner", 20)).unwrap().unwrap();
+    assert_eq!(conflict.kind, ConflictKind::Shadowing);
+    assert_eq!(format!("{}", conflict),
+        "x shadows an outer definition (previously defined at 0..1)");
+    assert_eq!(inner.borrow().resolve(&ident("x")), Some(x("inner", 20)));
+
+    inner.borrow_mut().define_policy.redefinition = ConflictPolicy::Allow;
+    assert_eq!(inner.borrow_mut().define_checked(ident("x"), x("inner2", 30)), Ok(None));
+    assert_eq!(inner.borrow().resolve(&ident("x")), Some(x("inner2", 30)));
+
+    // pushing from a mutably-borrowed scope falls back to the default policy
+    let walker = global.borrow_mut();
+    let pushed = global.push();
+    assert_eq!(pushed.borrow().define_policy, DefinePolicy::default());
+    drop(walker);
+}
+
+#[test]
+fn test_resolve_with_location() {
+    let global = Rc::new(RefCell::new(SymbolScope::<&str>::default()));
+    global.borrow_mut().define(ident("a"), "a");
+    global.borrow_mut().define(ident("b"), "b");
+    let frame = global.push();
+    frame.borrow_mut().define(ident("c"), "c");
+    let block = frame.push();
+    block.borrow_mut().define(ident("d"), "d");
+    block.borrow_mut().define(ident("a"), "inner a");
+
+    let loc = |depth, slot| SymbolLocation { depth, slot };
+    assert_eq!(block.borrow().resolve_with_location(&ident("a")), Some(("inner a", loc(0, 1))));
+    assert_eq!(block.borrow().resolve_with_location(&ident("b")), Some(("b", loc(2, 1))));
+    assert_eq!(block.borrow().resolve_with_location(&ident("c")), Some(("c", loc(1, 0))));
+    assert_eq!(block.borrow().resolve_with_location(&ident("z")), None);
+
+    // slots are stable across redefinition
+    block.borrow_mut().define(ident("d"), "new d");
+    assert_eq!(block.borrow().resolve_with_location(&ident("d")), Some(("new d", loc(0, 0))));
+
+    // imported symbols have no static location
+    let other = global.push();
+    other.borrow_mut().define(ident("e"), "e");
+    block.borrow_mut().import(Import::name(Rc::clone(&other), ident("e"))).unwrap();
+    assert_eq!(block.borrow().resolve(&ident("e")), Some("e"));
+    assert_eq!(block.borrow().resolve_with_location(&ident("e")), None);
+
+    let memory = Rc::new(RefCell::new(MemoryScope::<&str, i64>::default()));
+    memory.borrow_mut().define(ident("x"), "x");
+    memory.borrow_mut().define(ident("y"), "y");
+    assert_eq!(memory.push().borrow().resolve_with_location(&ident("y")), Some(("y", loc(1, 1))));
+}
+
+#[test]
+fn test_captures() {
+    let global = Rc::new(RefCell::new(SymbolScope::<&str>::default()));
+    global.borrow_mut().define(ident("g"), "g");
+    let outer = global.push();
+    outer.borrow_mut().kind = ScopeKind::Function;
+    outer.borrow_mut().define(ident("x"), "x");
+    outer.borrow_mut().define(ident("y"), "y");
+    let block = outer.push();
+    let lambda = block.push();
+    lambda.borrow_mut().kind = ScopeKind::Function;
+    lambda.borrow_mut().define(ident("arg"), "arg");
+    let body = lambda.push();
+
+    assert_eq!(body.resolve_read(&ident("arg")), Some("arg"));
+    assert_eq!(body.resolve_read(&ident("x")), Some("x"));
+    assert_eq!(body.resolve_write(&ident("y")), Some("y"));
+    assert_eq!(body.resolve_read(&ident("y")), Some("y"));
+    assert_eq!(body.resolve_write(&ident("g")), Some("g"));
+    assert_eq!(body.resolve_read(&ident("missing")), None);
+
+    let lambda = lambda.borrow();
+    assert_eq!(lambda.captures.len(), 3);
+    assert_eq!(lambda.captured(&ident("arg")), None);
+    assert_eq!(lambda.captured(&ident("x")), Some(Access::Read));
+    assert_eq!(lambda.captured(&ident("y")), Some(Access::ReadWrite));
+    assert_eq!(lambda.captured(&ident("g")), Some(Access::Write));
+    // captures beyond the enclosing function are recorded there as well
+    let outer = outer.borrow();
+    assert_eq!(outer.captures.len(), 1);
+    assert_eq!(outer.captured(&ident("g")), Some(Access::Write));
+    assert!(block.borrow().captures.is_empty());
+}
+
+#[test]
+fn test_stack() {
+    let global = Rc::new(RefCell::new(SymbolScope::<&str>::default()));
+    let mut current = Rc::clone(&global);
+    current = current.push();
+    current.borrow_mut().define(ident("x"), "x");
+    let inner = Rc::clone(&current);
+
+    // peek leaves the current scope alone
+    assert!(Rc::ptr_eq(&current.peek().unwrap(), &global));
+    assert!(Rc::ptr_eq(&current, &inner));
+
+    // pop moves to the parent, returning the popped scope
+    let popped = current.pop().unwrap();
+    assert!(Rc::ptr_eq(&popped, &inner));
+    assert!(Rc::ptr_eq(&current, &global));
+    assert!(current.pop().is_none());
+    assert!(Rc::ptr_eq(&current, &global));
+}
+
+#[test]
+fn test_scope_guard() {
+    fn walk(current: &mut Rc<RefCell<SymbolScope<&'static str>>>, fail: bool)
+        -> Result<(), String>
+    {
+        let guard = ScopeGuard::push(current);
+        guard.borrow_mut().define(ident("local"), "local");
+        if fail {
+            return Err("early return".to_string());
+        }
+        assert_eq!(guard.borrow().resolve(&ident("local")), Some("local"));
+        Ok(())
+    }
+
+    let global = Rc::new(RefCell::new(SymbolScope::<&str>::default()));
+    let mut current = Rc::clone(&global);
+    assert!(walk(&mut current, false).is_ok());
+    assert!(Rc::ptr_eq(&current, &global));
+    assert!(walk(&mut current, true).is_err());
+    assert!(Rc::ptr_eq(&current, &global));
+    assert_eq!(current.borrow().resolve(&ident("local")), None);
+}
+
+#[test]
+fn test_scope_tree() {
+    // fn f() { { let x; } { let y; } }
+    let global = Rc::new(RefCell::new(SymbolScope::<&str>::default()));
+    let mut tree = ScopeTree::new();
+    let root = tree.record(&global, "program", Some(Span::new(0, 40)));
+    let func = tree.push(&global, "fn f", Some(Span::new(7, 40)));
+    let first = tree.push(&func, "block 1", Some(Span::new(9, 21)));
+    first.borrow_mut().define(ident("x"), "x");
+    let second = tree.push(&func, "block 2", Some(Span::new(22, 38)));
+    second.borrow_mut().define(ident("y"), "y");
+
+    assert_eq!(tree.len(), 4);
+    assert_eq!(tree.roots().collect::<Vec<_>>(), vec![root]);
+    let func_id = tree.id_of(&func).unwrap();
+    assert_eq!(tree.children(root), &[func_id]);
+    let children = tree.children(func_id).to_vec();
+    assert_eq!(children.len(), 2);
+    assert_eq!(tree.get(children[1]).unwrap().node, "block 2");
+    assert_eq!(tree.get(children[1]).unwrap().parent, Some(func_id));
+
+    let enclosing = tree.enclosing(25).unwrap();
+    assert_eq!(enclosing, children[1]);
+    let scope = tree.scope(enclosing).unwrap();
+    assert_eq!(scope.borrow().resolve(&ident("y")), Some("y"));
+    assert_eq!(scope.borrow().resolve(&ident("x")), None);
+    assert_eq!(tree.enclosing(8), Some(func_id));
+    assert_eq!(tree.enclosing(3), Some(root));
+    assert_eq!(tree.enclosing(50), None);
+
+    // scopes whose parent was never recorded are linked to their nearest recorded ancestor
+    let unrecorded = second.push();
+    let inner = tree.push(&unrecorded, "block 3", Some(Span::new(30, 36)));
+    let inner_id = tree.id_of(&inner).unwrap();
+    assert_eq!(tree.get(inner_id).unwrap().parent, Some(children[1]));
+    assert_eq!(tree.get(inner_id).unwrap().depth, 3);
+    assert_eq!(tree.roots().collect::<Vec<_>>(), vec![root]);
+
+    // overlapping spans at the same depth: the smallest span wins
+    let wide = tree.push(&func, "wide", Some(Span::new(9, 38)));
+    let wide_id = tree.id_of(&wide).unwrap();
+    assert_eq!(tree.get(wide_id).unwrap().depth, tree.get(children[0]).unwrap().depth);
+    assert_eq!(tree.enclosing(12), Some(children[0]));
+    assert_eq!(tree.enclosing(21), Some(wide_id));
+}
+
+#[test]
+fn test_visible_symbols() {
+    let global = Rc::new(RefCell::new(SymbolScope::<&str>::default()));
+    global.borrow_mut().define(ident("count"), "global count");
+    global.borrow_mut().define(ident("total"), "total");
+    let module = global.push();
+    module.borrow_mut().define(ident("helper"), "helper");
+    let block = global.push();
+    block.borrow_mut().define(ident("counter"), "counter");
+    block.borrow_mut().define(ident("count"), "local count");
+    block.borrow_mut().import(Import::glob(Rc::clone(&module))).unwrap();
+
+    let local = block.borrow().item.symbols().map(|(ident, _)| ident.as_str()).collect::<Vec<_>>();
+    assert_eq!(local, vec!["counter", "count"]);
+
+    let visible = block.borrow().visible_symbols();
+    assert_eq!(visible, vec![
+        (ident("counter"), "counter"),
+        (ident("count"), "local count"),
+        (ident("helper"), "helper"),
+        (ident("total"), "total"),
+    ]);
+
+    assert_eq!(block.borrow().resolve(&ident("coutn")), None);
+    assert_eq!(block.borrow().suggest(&ident("coutn")), vec![ident("count")]);
+    assert_eq!(block.borrow().suggest(&ident("countr")), vec![ident("count"), ident("counter")]);
+    assert_eq!(block.borrow().suggest(&ident("helpr")), vec![ident("helper")]);
+    assert!(block.borrow().suggest(&ident("xyz")).is_empty());
+
+    // identifiers with the same text in other syntax contexts are suggested once
+    let marked = ident("counter").with_context(SyntaxContext::fresh());
+    block.borrow_mut().define(marked, "marked counter");
+    let suggested = block.borrow().suggest(&ident("countr"));
+    assert_eq!(suggested.iter().map(Identifier::as_str).collect::<Vec<_>>(),
+        vec!["count", "counter"]);
+    assert_eq!(block.borrow().suggest(&ident("counter")), vec![ident("count")]);
+}
+
+#[test]
+fn test_persistent_scope() {
+    let mut scope = PersistentScope::<&str, i64>::new();
+    scope.define(ident("x"), "x");
+    scope.set(ident("x"), 1).unwrap();
+    let mut inner = scope.push();
+    inner.define(ident("y"), "y");
+    inner.set(ident("y"), 2).unwrap();
+
+    let snapshot = inner.snapshot();
+    inner.set(ident("x"), 10).unwrap();
+    inner.define(ident("z"), "z");
+    assert_eq!(inner.get(&ident("x")), Some(10));
+    assert_eq!(inner.resolve(&ident("z")), Some("z"));
+    // snapshots and other handles are unaffected by changes
+    assert_eq!(snapshot.get(&ident("x")), Some(1));
+    assert_eq!(snapshot.resolve(&ident("z")), None);
+    assert_eq!(scope.get(&ident("x")), Some(1));
+
+    inner.rollback(snapshot);
+    assert_eq!(inner.get(&ident("x")), Some(1));
+    assert_eq!(inner.resolve(&ident("z")), None);
+    assert!(inner.set(ident("z"), 3).is_err());
+
+    assert_eq!(inner.depth(), 2);
+    assert_eq!(inner.resolve_with_location(&ident("x")),
+        Some(("x", SymbolLocation { depth: 1, slot: 0 })));
+    let popped = inner.pop().unwrap();
+    assert_eq!(popped.get(&ident("y")), Some(2));
+    assert_eq!(inner.get(&ident("y")), None);
+    assert_eq!(inner.depth(), 1);
+
+    // slots remain stable (and in definition order) when redefining after a snapshot
+    let before = inner.snapshot();
+    inner.define(ident("w"), "w");
+    assert_eq!(inner.define(ident("x"), "x2"), Some("x"));
+    assert_eq!(inner.resolve_with_location(&ident("x")),
+        Some(("x2", SymbolLocation { depth: 0, slot: 0 })));
+    assert_eq!(inner.visible_symbols(), vec![(ident("x"), "x2"), (ident("w"), "w")]);
+    assert_eq!(before.visible_symbols(), vec![(ident("x"), "x")]);
+}
+
+#[test]
+fn test_hygiene() {
+    let global = Rc::new(RefCell::new(SymbolScope::<Symbol>::default()));
+    global.borrow_mut().define(ident("tmp"), Symbol::Field("user tmp"));
+
+    // expansion of a macro which introduces its own `tmp` binding, and refers to `tmp` both from
+    // its own body and from a macro argument
+    let expansion = SyntaxContext::fresh();
+    let block = global.push();
+    block.borrow_mut().define(ident("tmp").with_context(expansion), Symbol::Field("macro tmp"));
+    assert_eq!(block.borrow().resolve(&ident("tmp").with_context(expansion)),
+        Some(Symbol::Field("macro tmp")));
+    assert_eq!(block.borrow().resolve(&ident("tmp")), Some(Symbol::Field("user tmp")));
+    // marked identifiers without a binding of their own resolve in the outer context
+    assert_eq!(block.borrow().resolve(&ident("tmp").with_context(SyntaxContext::fresh())),
+        Some(Symbol::Field("user tmp")));
+    let nested = expansion.nested();
+    assert_eq!(block.push().borrow().resolve(&ident("tmp").with_context(nested)),
+        Some(Symbol::Field("macro tmp")));
+    assert_eq!(block.borrow().resolve(&ident("missing").with_context(nested)), None);
+    assert_eq!(block.borrow().try_resolve(&ident("missing").with_context(nested)),
+        Err(ResolveError::Undefined(ident("missing").with_context(nested))));
+    // a binding in the marked context takes precedence, even if the outer binding is closer
+    let inner = block.push();
+    inner.borrow_mut().define(ident("tmp"), Symbol::Field("inner tmp"));
+    assert_eq!(inner.borrow().resolve(&ident("tmp").with_context(expansion)),
+        Some(Symbol::Field("macro tmp")));
+
+    let fresh = Identifier::gensym("tmp");
+    block.borrow_mut().define(fresh, Symbol::Field("generated"));
+    assert_eq!(block.borrow().resolve(&fresh), Some(Symbol::Field("generated")));
+    assert_eq!(block.borrow().resolve(&ident("tmp")), Some(Symbol::Field("user tmp")));
+}
@@ -0,0 +1,208 @@
+#![cfg(feature = "serde")]
+
+#[macro_use] extern crate serde;
+extern crate serde_json;
+extern crate bincode;
+#[macro_use] extern crate sindra;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use sindra::scope::{AccessStore, DeclarationStore, Import, MemberStore, MemoryScope, MemoryStore,
+    MemorySnapshot, Mutability, ResolveError, Shared, SlotTable, Stack, SymbolScope, SymbolStore,
+    Visibility, Visible};
+use sindra::{Identifier, IdentPath, Node, Span};
+
+fn ident(name: &str) -> Identifier { Identifier::new(name) }
+
+#[test]
+fn scope_graph() {
+    let global = Rc::new(RefCell::new(SymbolScope::<String>::default()));
+    global.borrow_mut().define(ident("g"), "global".to_string());
+    let namespace = global.push();
+    namespace.borrow_mut().define(ident("n"), "namespaced".to_string());
+    let base = global.push();
+    base.borrow_mut().define(ident("b"), "base".to_string());
+
+    let inner = global.push();
+    inner.borrow_mut().add_base(Rc::clone(&base));
+    inner.borrow_mut().import(Import::glob(Rc::clone(&namespace))).unwrap();
+    inner.borrow_mut().define(ident("i"), "inner".to_string());
+    let inner = inner.borrow();
+
+    let json = serde_json::to_string(&*inner).unwrap();
+    let restored: SymbolScope<String> = serde_json::from_str(&json).unwrap();
+    for name in &["g", "n", "b", "i"] {
+        assert_eq!(restored.resolve(&ident(name)), inner.resolve(&ident(name)));
+    }
+    assert_eq!(restored.resolve_member(&ident("b")), Some("base".to_string()));
+
+    // scopes shared through several links are restored as a single scope
+    let parent = restored.parent.clone().unwrap();
+    assert!(Rc::ptr_eq(&parent, restored.bases[0].borrow().parent.as_ref().unwrap()));
+    parent.borrow_mut().define(ident("late"), "late".to_string());
+    assert_eq!(restored.bases[0].borrow().resolve(&ident("late")), Some("late".to_string()));
+
+    assert!(serde_json::from_str::<SymbolScope<String>>("[]").is_err());
+}
+
+#[test]
+fn memory_scope() {
+    let global = Rc::new(RefCell::new(MemoryScope::<String, i64>::default()));
+    global.borrow_mut().declare_with(ident("k"), "const".to_string(),
+        Mutability::Const, Some(1)).unwrap();
+    let inner = global.push();
+    inner.borrow_mut().declare(ident("x"), "var".to_string());
+
+    let json = serde_json::to_string(&*inner.borrow()).unwrap();
+    let mut restored: MemoryScope<String, i64> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.read(&ident("k")), Ok(1));
+    assert!(restored.set(ident("k"), 2).is_err());
+    assert_eq!(restored.set(ident("x"), 3), Ok(None));
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Literal(i64);
+annotate!(Literal, Option<String>);
+
+#[test]
+fn node() {
+    let node = Node::with_span(Literal(4), Span::new(2, 3));
+    *node.annotation.borrow_mut() = Some("int".to_string());
+    let json = serde_json::to_string(&node).unwrap();
+    let restored: Node<Literal> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, node);
+}
+
+#[test]
+fn hygienic_identifier() {
+    let marked = Identifier::gensym("tmp");
+    let json = serde_json::to_string(&(ident("tmp"), marked)).unwrap();
+    let (plain, restored): (Identifier, Identifier) = serde_json::from_str(&json).unwrap();
+    assert_eq!(plain, ident("tmp"));
+    assert_eq!(restored, marked);
+    assert_ne!(Identifier::gensym("tmp"), restored);
+    let (_, again): (Identifier, Identifier) = serde_json::from_str(&json).unwrap();
+    assert_eq!(again, restored);
+
+    // contexts from another process are consistently mapped to fresh contexts of this process
+    let foreign = r#"[["tmp", [1, 42]], ["x", [1, 42]], ["tmp", [2, 42]]]"#;
+    let first: Vec<Identifier> = serde_json::from_str(foreign).unwrap();
+    let second: Vec<Identifier> = serde_json::from_str(foreign).unwrap();
+    assert_eq!(first, second);
+    assert_eq!(first[0].context(), first[1].context());
+    assert_ne!(first[0], first[2]);
+    assert_eq!(first[0].as_str(), "tmp");
+    assert!(!first[0].context().is_root());
+    assert_ne!(first[0], marked);
+
+    // contexts claiming to be from this process must exist
+    let mut value = serde_json::to_value(marked).unwrap();
+    value[1][0] = serde_json::Value::from(u32::MAX);
+    assert!(serde_json::from_value::<Identifier>(value).is_err());
+}
+
+#[test]
+fn non_self_describing() {
+    let marked = Identifier::gensym("tmp");
+    let bytes = bincode::serialize(&(ident("tmp"), marked)).unwrap();
+    let (plain, restored): (Identifier, Identifier) = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(plain, ident("tmp"));
+    assert_eq!(restored, marked);
+
+    let path = IdentPath::parse("std::io::Write").unwrap();
+    let bytes = bincode::serialize(&path).unwrap();
+    assert_eq!(bincode::deserialize::<IdentPath>(&bytes).unwrap(), path);
+
+    let global = Rc::new(RefCell::new(MemoryScope::<String, i64>::default()));
+    global.borrow_mut().declare_init(ident("x"), "var".to_string(), 1);
+    let inner = global.push();
+    inner.borrow_mut().declare_init(marked, "marked".to_string(), 2);
+    let bytes = bincode::serialize(&*inner.borrow()).unwrap();
+    let restored: MemoryScope<String, i64> = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(restored.read(&ident("x")), Ok(1));
+    assert_eq!(restored.read(&marked), Ok(2));
+
+    let snapshot = MemorySnapshot::new(&inner.borrow());
+    let bytes = bincode::serialize(&snapshot).unwrap();
+    let restored: MemorySnapshot<String, i64> = bincode::deserialize(&bytes).unwrap();
+    assert!(snapshot.diff(&restored).is_empty());
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+enum Item {
+    Value(String),
+    Scope(#[serde(with = "sindra::scope::serialize")] Rc<RefCell<SymbolScope<Item>>>),
+}
+
+fn member_scope(item: Option<Item>) -> Rc<RefCell<SymbolScope<Item>>> {
+    match item {
+        Some(Item::Scope(scope)) => scope,
+        _ => panic!("expected scope item"),
+    }
+}
+
+#[test]
+fn class_namespace_graph() {
+    // namespace ns { class Class { field } }, where each member scope's parent is the scope
+    // defining it
+    let global = Rc::new(RefCell::new(SymbolScope::<Item>::default()));
+    let namespace = global.push();
+    global.borrow_mut().define(ident("ns"), Item::Scope(Rc::clone(&namespace)));
+    let class = namespace.push();
+    namespace.borrow_mut().define(ident("Class"), Item::Scope(Rc::clone(&class)));
+    class.borrow_mut().define(ident("field"), Item::Value("field".to_string()));
+    let user = global.push();
+    user.borrow_mut().import(Import::glob(Rc::clone(&namespace))).unwrap();
+    global.borrow_mut().define(ident("user"), Item::Scope(Rc::clone(&user)));
+
+    // the global scope is referred to by the scopes it contains, so can't be serialised by value
+    assert!(serde_json::to_string(&*global.borrow()).is_err());
+
+    let json = serde_json::to_string(&Shared(Rc::clone(&global))).unwrap();
+    let Shared(restored) = serde_json::from_str::<Shared<SlotTable<Item>>>(&json).unwrap();
+    let namespace = member_scope(restored.borrow().resolve(&ident("ns")));
+    assert!(Rc::ptr_eq(namespace.borrow().parent.as_ref().unwrap(), &restored));
+    let class = member_scope(namespace.borrow().resolve(&ident("Class")));
+    assert!(Rc::ptr_eq(class.borrow().parent.as_ref().unwrap(), &namespace));
+    match class.borrow().resolve(&ident("field")) {
+        Some(Item::Value(ref value)) => assert_eq!(value, "field"),
+        _ => panic!("expected field"),
+    }
+    // the imported namespace is restored as the same scope as the namespace symbol's scope
+    let user = member_scope(restored.borrow().resolve(&ident("user")));
+    let imported = member_scope(user.borrow().resolve(&ident("Class")));
+    assert!(Rc::ptr_eq(&imported, &class));
+    // and names in the global scope remain visible through the cycle
+    assert!(class.borrow().resolve(&ident("user")).is_some());
+
+    assert!(serde_json::from_str::<Shared<SlotTable<Item>>>("[1, null]").is_err());
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Member {
+    name: String,
+    visibility: Visibility,
+}
+impl Visible for Member {
+    fn visibility(&self) -> Visibility { self.visibility }
+}
+
+#[test]
+fn member_visibility() {
+    let class = Rc::new(RefCell::new(SymbolScope::<Member>::default()));
+    class.borrow_mut().define(ident("secret"),
+        Member { name: "secret".to_string(), visibility: Visibility::Private });
+    class.borrow_mut().define(ident("open"),
+        Member { name: "open".to_string(), visibility: Visibility::Public });
+    let derived = Rc::new(RefCell::new(SymbolScope::<Member>::default()));
+    derived.borrow_mut().add_base(class);
+
+    let json = serde_json::to_string(&*derived.borrow()).unwrap();
+    let restored: SymbolScope<Member> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.resolve_access(&ident("secret")),
+        Err(ResolveError::Inaccessible(ident("secret"))));
+    assert_eq!(restored.resolve_access(&ident("open")).map(|member| member.visibility),
+        Ok(Visibility::Public));
+    assert_eq!(serde_json::to_string(&Visibility::Private).unwrap(), "\"Private\"");
+}
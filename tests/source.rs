@@ -0,0 +1,34 @@
+extern crate sindra;
+
+use sindra::source::{FileId, Location, SourceMap};
+
+#[test]
+fn test_source_map() {
+    let mut map = SourceMap::new();
+    let a = map.add("a.lang", "let x = 1;\nlet y = 2;\n");
+    let b = map.add("<repl>", "\"☺\" + z");
+
+    assert_eq!(map.find("<repl>"), Some(b));
+    assert_eq!(map.file(a).unwrap().name(), "a.lang");
+
+    let b_start = map.span(b).unwrap().start;
+    assert_eq!(b_start, map.span(a).unwrap().end + 1);
+
+    assert_eq!(map.lookup_file(0), Some(a));
+    assert_eq!(map.lookup_file(b_start + 3), Some(b));
+    assert_eq!(map.lookup_file(b_start + 100), None);
+
+    assert_eq!(map.location(15), Some(Location { file: a, line: 1, column: 4 }));
+    assert_eq!(map.line_text(a, 1), Some("let y = 2;"));
+
+    // '☺' is three bytes in UTF-8, but one code unit in UTF-16
+    let z = b_start + 9;
+    assert_eq!(map.location(z), Some(Location { file: b, line: 0, column: 9 }));
+    assert_eq!(map.location_utf16(z), Some(Location { file: b, line: 0, column: 7 }));
+    assert_eq!(map.location_utf16(b_start + 2), None);
+
+    assert_eq!(map.offset(Location { file: a, line: 1, column: 4 }), Some(15));
+    assert_eq!(map.offset_utf16(Location { file: b, line: 0, column: 7 }), Some(z));
+    assert_eq!(map.offset(Location { file: FileId(5), line: 0, column: 0 }), None);
+    assert_eq!(format!("{}", map.location(15).unwrap()), "2:5");
+}
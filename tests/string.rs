@@ -13,9 +13,17 @@ fn test_string() {
         let converted = convert_string(mat.1);
         assert!(converted.is_ok());
         assert_eq!(converted.unwrap(), expected);
-    };
+    }
     assert_string(r#""foo""#, "foo");
     assert_string(r#""\x41\x2D\x5A""#, "A-Z");
     assert_string(r#""\u{263A}\u{2639}""#, "☺☹");
     assert_string(r#""☺☹""#, "☺☹");
 }
+
+#[test]
+fn test_string_ext() {
+    assert_eq!(string_ext(r#"x = "foo\n";"#, 4), Ok((11, "foo\n".to_string())));
+    assert!(string_ext("x = 4;", 4).is_err());
+    assert_eq!(string(r#""foo""#), Ok("foo".to_string()));
+    assert!(string(r#""foo" "#).is_err());
+}